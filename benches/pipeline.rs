@@ -0,0 +1,201 @@
+//! Benchmarks for the GIF tile pipeline
+//!
+//! Run with `cargo bench`. Groups that replaced an older approach
+//! benchmark it as well, rebuilt from the public API, so the speedup can
+//! be reproduced on any machine.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use gif::{Encoder, Frame, Repeat};
+use gridist::animation;
+use gridist::cropper::ImageCropper;
+use gridist::quantize::{self, PaletteLookup};
+use gridist::simd::{self, PaletteLanes};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use kdtree::distance::squared_euclidean;
+use kdtree::KdTree;
+use std::hint::black_box;
+
+/// Frames of the benchmark animation
+const FRAMES: u16 = 200;
+
+/// Encodes a 480x270 GIF with a drifting gradient and a moving block
+fn moving_gif() -> Vec<u8> {
+    let (width, height) = (480u16, 270u16);
+    let palette: Vec<u8> = (0..=255u8)
+        .flat_map(|i| [i, 255 - i, i.wrapping_mul(7)])
+        .collect();
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut bytes, width, height, &palette).unwrap();
+        encoder.set_repeat(Repeat::Infinite).unwrap();
+        for t in 0..FRAMES {
+            let (block_x, block_y) = (t * 2 % (width - 60), t % (height - 60));
+            let pixels: Vec<u8> = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let in_block = (block_x..block_x + 60).contains(&x)
+                        && (block_y..block_y + 60).contains(&y);
+                    if in_block {
+                        255
+                    } else {
+                        ((x / 2 + y + t * 3) % 255) as u8
+                    }
+                })
+                .collect();
+            let mut frame = Frame::from_indexed_pixels(width, height, pixels, None);
+            frame.delay = 4;
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    bytes
+}
+
+/// Decodes the composited frames of an animation
+fn decode_frames(bytes: &[u8]) -> Vec<DynamicImage> {
+    let mut source = animation::open(bytes, ImageFormat::Gif).unwrap().unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = source.next_frame().unwrap() {
+        frames.push(DynamicImage::ImageRgba8(frame.image));
+    }
+    frames
+}
+
+/// Splitting a 200-frame GIF, and resizing each frame once rather than once per tile
+fn gif_frames(c: &mut Criterion) {
+    let bytes = moving_gif();
+    let frames = decode_frames(&bytes);
+    let cropper = ImageCropper::default();
+    let tile_count = cropper.config().tile_count() as usize;
+
+    let mut group = c.benchmark_group("gif_200_frames");
+    group.sample_size(10);
+    group.bench_function("crop_gif_bytes", |b| {
+        b.iter(|| cropper.crop_gif_bytes(black_box(&bytes)).unwrap())
+    });
+    group.finish();
+
+    // Per frame, so the totals are these times 200
+    let mut group = c.benchmark_group("gif_frame_resize");
+    let mut frame = frames.iter().cycle();
+    group.bench_function("once_per_frame", |b| {
+        b.iter(|| cropper.crop_tiles(black_box(frame.next().unwrap())))
+    });
+    group.bench_function("once_per_tile", |b| {
+        b.iter(|| {
+            let frame = frame.next().unwrap();
+            (0..tile_count)
+                .map(|i| cropper.crop_tiles(black_box(frame)).swap_remove(i))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+/// Cuts the first tile of a frame of the benchmark animation, 422x100 by default
+fn sample_tile() -> RgbaImage {
+    let frames = decode_frames(&moving_gif());
+    ImageCropper::default()
+        .crop_tiles(&frames[0])
+        .swap_remove(0)
+}
+
+/// Nearest-colour matching of a tile through the cached cube and through a KD-tree
+fn palette_lookup(c: &mut Criterion) {
+    let tile = sample_tile();
+    let palette = quantize::median_cut(&quantize::sample_colors(&[&tile]), 256);
+
+    let mut group = c.benchmark_group("palette_lookup");
+    group.bench_function("cube_build", |b| {
+        b.iter(|| PaletteLookup::new(black_box(&palette), None))
+    });
+    // Cells are filled on first use, so each run starts from a fresh lookup
+    group.bench_function("cube_tile", |b| {
+        b.iter_batched(
+            || PaletteLookup::new(&palette, None),
+            |lookup| {
+                tile.pixels()
+                    .map(|pixel| lookup.index(black_box(pixel.0)))
+                    .collect::<Vec<_>>()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    // The KD-tree used to be rebuilt for every frame of every tile
+    group.bench_function("kdtree_tile", |b| {
+        b.iter(|| {
+            let mut tree = KdTree::new(3);
+            for (index, color) in palette.chunks_exact(3).enumerate() {
+                let point = [color[0] as f32, color[1] as f32, color[2] as f32];
+                tree.add(point, index as u8).unwrap();
+            }
+            tile.pixels()
+                .map(|pixel| {
+                    let [r, g, b, _] = black_box(pixel.0);
+                    *tree
+                        .nearest(&[r as f32, g as f32, b as f32], 1, &squared_euclidean)
+                        .unwrap()[0]
+                        .1
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+/// The SIMD helpers on a 422x100 tile, next to the scalar code they replaced
+fn simd_tile(c: &mut Criterion) {
+    let tile = sample_tile();
+    let palette = quantize::median_cut(&quantize::sample_colors(&[&tile]), 256);
+    let colors: Vec<[u8; 3]> = palette
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect();
+
+    let mut group = c.benchmark_group("simd_tile");
+    group.bench_function("opaque_mask", |b| {
+        b.iter(|| {
+            black_box(tile.as_raw())
+                .chunks(32)
+                .map(simd::opaque_mask)
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("opaque_scalar", |b| {
+        b.iter(|| {
+            black_box(tile.as_raw())
+                .chunks_exact(4)
+                .map(|pixel| pixel[3] >= 128)
+                .collect::<Vec<_>>()
+        })
+    });
+
+    let lanes = PaletteLanes::new(&colors, None);
+    group.bench_function("nearest_lanes", |b| {
+        b.iter(|| {
+            tile.pixels()
+                .map(|pixel| lanes.nearest(black_box([pixel[0], pixel[1], pixel[2]])))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("nearest_scalar", |b| {
+        b.iter(|| {
+            tile.pixels()
+                .map(|pixel| {
+                    let [r, g, b, _] = black_box(pixel.0).map(i32::from);
+                    colors
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, c)| {
+                            let [cr, cg, cb] = c.map(i32::from);
+                            (cr - r).pow(2) + (cg - g).pow(2) + (cb - b).pow(2)
+                        })
+                        .map_or(0, |(index, _)| index as u8)
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, gif_frames, palette_lookup, simd_tile);
+criterion_main!(benches);
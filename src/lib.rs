@@ -3,13 +3,22 @@ Gridist - A tool for creating and managing grid-based image layouts on GitHub Gi
 
 This library provides functionality to:
 1. Process images and GIFs into grid layouts
-2. Upload processed images to GitHub Gists
-3. Manage uploaded gists through a TUI interface
+2. Preview the resulting grid locally
+3. Upload processed images to GitHub Gists
+4. Manage uploaded gists through a TUI interface
 
 # Main Components
 
 - `config`: Configuration settings for image processing and layout
 - `cropper`: Image and GIF processing functionality
+- `animation`: Decoding and compositing of GIF, APNG and WebP animations
+- `quantize`: Colour quantization for GIF output
+- `dither`: Dithering for GIF palette mapping
+- `simd`: SIMD helpers for colour conversion
+- `saliency`: Edge-based saliency for placing the crop
+- `budget`: File-size budget for tiles
+- `encode`: Output formats and encoder settings for still tiles
+- `preview`: Local rendering of the grid on a mocked profile
 - `github`: GitHub Gist API interaction and file management
 - `tui`: Terminal user interface for gist management
 
@@ -23,10 +32,8 @@ use anyhow::Context;
 use arboard::Clipboard;
 use gif::{Decoder, Encoder, Frame, Repeat};
 use git2::{Cred, RemoteCallbacks, Signature};
-use image::{GenericImageView, RgbaImage};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use kdtree::distance::squared_euclidean;
-use kdtree::KdTree;
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use rayon::prelude::*;
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
@@ -34,12 +41,11 @@ use serde_json::json;
 use std::borrow::Cow;
 use std::fs;
 use std::fs::File;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::Mutex;
 use tempfile::TempDir;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Custom error types for Gridist operations
 #[derive(Error, Debug)]
@@ -62,6 +68,21 @@ pub enum GridistError {
     #[error("Invalid file name: {0}")]
     InvalidFileName(String),
 
+    #[error("Invalid layout: {0}")]
+    LayoutError(String),
+
+    #[error("Invalid image configuration: {}", .0.join("; "))]
+    InvalidConfig(Vec<String>),
+
+    #[error("Invalid animation timing: {0}")]
+    InvalidTiming(String),
+
+    #[error("Invalid size: {0}")]
+    InvalidSize(String),
+
+    #[error("Unsupported image format: {0}")]
+    UnsupportedFormat(String),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -71,8 +92,24 @@ pub type GridistResult<T> = std::result::Result<T, GridistError>;
 
 /// Configuration settings for image processing and layout
 pub mod config {
+    use crate::{GridistError, GridistResult};
+    use clap::ValueEnum;
+    use image::imageops::FilterType;
+    use serde::{Deserialize, Serialize};
+    use std::fmt;
+    use std::fs;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    /// Names of the built-in layout presets
+    pub const PRESETS: &[&str] = &["github-pinned-2024", "github-pinned-compact"];
+
     /// Configuration for image dimensions and spacing
-    #[derive(Debug, Clone)]
+    ///
+    /// Layout files only need to list the fields that differ from the default.
+    /// Unknown fields are rejected, so a misspelt key isn't silently ignored.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
     pub struct ImageConfig {
         /// Width of the container that holds all cards
         pub container_width: u32,
@@ -88,6 +125,138 @@ pub mod config {
         pub card_padding_bottom: u32,
         /// Margin between cards
         pub card_margin_bottom: u32,
+        /// Number of card rows in the grid
+        pub rows: u32,
+        /// Number of card columns in the grid
+        pub cols: u32,
+        /// How the image is scaled to the grid
+        pub fit: Fit,
+        /// Fill around the image when it doesn't cover the grid
+        pub background: Background,
+        /// Resampling filter used when scaling
+        pub filter: ResizeFilter,
+    }
+
+    /// How an image is scaled to the grid
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Fit {
+        /// Scale to cover the grid, cropping what lies outside
+        #[default]
+        Cover,
+        /// Scale to fit inside the grid, filling the rest with the background
+        Contain,
+        /// Like contain, but never enlarge the image
+        Pad,
+        /// Scale each axis to the grid, ignoring the aspect ratio
+        Stretch,
+    }
+
+    /// Resampling filter used when scaling
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+    #[serde(rename_all = "lowercase")]
+    #[value(rename_all = "lower")]
+    pub enum ResizeFilter {
+        /// Nearest neighbour, keeping hard pixel edges
+        Nearest,
+        /// Linear interpolation
+        Triangle,
+        /// Cubic Catmull-Rom spline
+        CatmullRom,
+        /// Gaussian, slightly soft
+        Gaussian,
+        /// Lanczos with a window of 3, the sharpest
+        #[default]
+        Lanczos3,
+    }
+
+    impl ResizeFilter {
+        /// Returns the image crate's filter
+        pub fn filter_type(self) -> FilterType {
+            match self {
+                ResizeFilter::Nearest => FilterType::Nearest,
+                ResizeFilter::Triangle => FilterType::Triangle,
+                ResizeFilter::CatmullRom => FilterType::CatmullRom,
+                ResizeFilter::Gaussian => FilterType::Gaussian,
+                ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+            }
+        }
+    }
+
+    /// Fill around an image that doesn't cover the grid
+    ///
+    /// Written as `transparent`, `blur`, a colour name or `#rrggbb[aa]`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(try_from = "String", into = "String")]
+    pub enum Background {
+        /// A solid RGBA colour
+        Color([u8; 4]),
+        /// A blurred copy of the image scaled to cover the grid
+        Blur,
+    }
+
+    impl Default for Background {
+        fn default() -> Self {
+            Background::Color([0, 0, 0, 0])
+        }
+    }
+
+    impl FromStr for Background {
+        type Err = GridistError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || {
+                GridistError::InvalidConfig(vec![format!(
+                    "'{}' is not a background, use transparent, blur, white, black or #rrggbb[aa]",
+                    s
+                )])
+            };
+            let color = match s.trim().to_ascii_lowercase().as_str() {
+                "blur" => return Ok(Background::Blur),
+                "transparent" => [0, 0, 0, 0],
+                "white" => [255, 255, 255, 255],
+                "black" => [0, 0, 0, 255],
+                hex => {
+                    let hex = hex.strip_prefix('#').unwrap_or(hex);
+                    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+                        return Err(invalid());
+                    }
+                    let mut color = [255; 4];
+                    for (channel, i) in color.iter_mut().zip((0..hex.len()).step_by(2)) {
+                        *channel = u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid())?;
+                    }
+                    color
+                }
+            };
+            Ok(Background::Color(color))
+        }
+    }
+
+    impl fmt::Display for Background {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                Background::Blur => write!(f, "blur"),
+                Background::Color([0, 0, 0, 0]) => write!(f, "transparent"),
+                Background::Color([r, g, b, 255]) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+                Background::Color([r, g, b, a]) => {
+                    write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+                }
+            }
+        }
+    }
+
+    impl TryFrom<String> for Background {
+        type Error = GridistError;
+
+        fn try_from(value: String) -> Result<Self, Self::Error> {
+            value.parse()
+        }
+    }
+
+    impl From<Background> for String {
+        fn from(background: Background) -> Self {
+            background.to_string()
+        }
     }
 
     impl Default for ImageConfig {
@@ -100,11 +269,174 @@ pub mod config {
                 card_padding_horizontal: 16,
                 card_padding_bottom: 16,
                 card_margin_bottom: 16,
+                rows: 3,
+                cols: 2,
+                fit: Fit::default(),
+                background: Background::default(),
+                filter: ResizeFilter::default(),
             }
         }
     }
 
     impl ImageConfig {
+        /// Returns the built-in layout preset with the given name
+        ///
+        /// - `github-pinned-2024`: GitHub's pinned cards as of 2024 (the default)
+        /// - `github-pinned-compact`: Shorter cards with tighter paddings
+        pub fn preset(name: &str) -> Option<Self> {
+            match name {
+                "github-pinned-2024" => Some(Self::default()),
+                "github-pinned-compact" => Some(Self {
+                    cut_height: 80,
+                    card_padding_top: 16,
+                    card_padding_bottom: 12,
+                    card_margin_bottom: 12,
+                    ..Self::default()
+                }),
+                _ => None,
+            }
+        }
+
+        /// Loads a layout from a TOML or JSON file
+        ///
+        /// Files with a `.json` extension, in any case, are parsed as JSON,
+        /// anything else as TOML.
+        pub fn from_file(path: &Path) -> GridistResult<Self> {
+            let content = fs::read_to_string(path)?;
+            let config: Self = if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+            {
+                serde_json::from_str(&content)
+                    .map_err(|e| GridistError::LayoutError(format!("{}: {}", path.display(), e)))?
+            } else {
+                toml::from_str(&content)
+                    .map_err(|e| GridistError::LayoutError(format!("{}: {}", path.display(), e)))?
+            };
+            config.validate()?;
+            Ok(config)
+        }
+
+        /// Resolves a layout argument that is either a preset name or a file path
+        pub fn from_layout(layout: &str) -> GridistResult<Self> {
+            if let Some(config) = Self::preset(layout) {
+                return Ok(config);
+            }
+            let path = Path::new(layout);
+            if !path.exists() {
+                return Err(GridistError::LayoutError(format!(
+                    "'{}' is neither a file nor a preset (available presets: {})",
+                    layout,
+                    PRESETS.join(", ")
+                )));
+            }
+            Self::from_file(path)
+        }
+
+        /// Checks the geometry for inconsistencies
+        ///
+        /// Every problem found is reported at once through
+        /// [`GridistError::InvalidConfig`].
+        pub fn validate(&self) -> GridistResult<()> {
+            let mut problems = Vec::new();
+
+            if self.rows == 0 {
+                problems.push("rows must be at least 1".to_string());
+            }
+            if self.cols == 0 {
+                problems.push("cols must be at least 1".to_string());
+            }
+            if self.cut_width == 0 || self.cut_height == 0 {
+                problems.push(format!(
+                    "cut size must be non-zero, got {}x{}",
+                    self.cut_width, self.cut_height
+                ));
+            }
+            // GIF frames store their size in 16 bits
+            if self.cut_width > u16::MAX as u32 || self.cut_height > u16::MAX as u32 {
+                problems.push(format!(
+                    "cut size must be at most {0}x{0}, got {1}x{2}",
+                    u16::MAX,
+                    self.cut_width,
+                    self.cut_height
+                ));
+            }
+
+            // Custom layouts can hold any u32, so the derived sizes are checked
+            // before anything else computes them
+            if self.rows.checked_mul(self.cols).is_none() {
+                problems.push(format!(
+                    "{} rows of {} columns are too many tiles",
+                    self.rows, self.cols
+                ));
+            }
+            if self.checked_minimum_height().is_none() {
+                problems.push(format!(
+                    "{} rows of {}px cuts with {}px top, {}px bottom padding and {}px margins are too tall",
+                    self.rows,
+                    self.cut_height,
+                    self.card_padding_top,
+                    self.card_padding_bottom,
+                    self.card_margin_bottom
+                ));
+            }
+
+            let card_width = self
+                .card_padding_horizontal
+                .checked_mul(2)
+                .and_then(|padding| padding.checked_add(self.cut_width));
+            match card_width {
+                None => problems.push(format!(
+                    "{}px cut with {}px horizontal padding is too wide",
+                    self.cut_width, self.card_padding_horizontal
+                )),
+                Some(card_width) if card_width > self.container_width => problems.push(format!(
+                    "{}px cut with {}px horizontal padding lies outside the {}px container",
+                    self.cut_width, self.card_padding_horizontal, self.container_width
+                )),
+                Some(card_width) if self.cols > 1 => {
+                    if !matches!(
+                        self.cols.checked_mul(card_width),
+                        Some(width) if width <= self.container_width
+                    ) {
+                        problems.push(format!(
+                            "{} columns of {}px cards do not fit in the {}px container",
+                            self.cols, card_width, self.container_width
+                        ));
+                    } else if (self.cols - 1)
+                        .checked_mul(self.container_width - card_width)
+                        .is_none()
+                    {
+                        problems.push(format!(
+                            "{} columns spread over the {}px container are too many",
+                            self.cols, self.container_width
+                        ));
+                    }
+                }
+                Some(_) => {}
+            }
+
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(GridistError::InvalidConfig(problems))
+            }
+        }
+
+        /// Calculates the minimum height, or `None` if it or the card step overflows
+        fn checked_minimum_height(&self) -> Option<u32> {
+            let card_height = self
+                .card_padding_top
+                .checked_add(self.cut_height)?
+                .checked_add(self.card_padding_bottom)?;
+            card_height.checked_add(self.card_margin_bottom)?;
+            self.rows.checked_mul(card_height)?.checked_add(
+                self.rows
+                    .saturating_sub(1)
+                    .checked_mul(self.card_margin_bottom)?,
+            )
+        }
+
         /// Calculates the total height of a card including content and padding
         pub fn card_height(&self) -> u32 {
             self.card_padding_top + self.cut_height + self.card_padding_bottom
@@ -115,9 +447,105 @@ pub mod config {
             self.card_height() + self.card_margin_bottom
         }
 
+        /// Calculates the total number of tiles in the grid
+        pub fn tile_count(&self) -> u32 {
+            self.rows * self.cols
+        }
+
+        /// Calculates the width spanned by the cards of the grid
+        ///
+        /// A single column only covers one card, while multiple columns
+        /// stretch across the whole container.
+        pub fn grid_width(&self) -> u32 {
+            if self.cols > 1 {
+                self.container_width
+            } else {
+                self.cut_width + 2 * self.card_padding_horizontal
+            }
+        }
+
         /// Calculates the minimum height required for a grid layout
         pub fn minimum_height(&self) -> u32 {
-            3 * self.card_height() + 2 * self.card_margin_bottom
+            self.rows * self.card_height() + self.rows.saturating_sub(1) * self.card_margin_bottom
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn validate_json(json: &str) -> GridistResult<()> {
+            serde_json::from_str::<ImageConfig>(json)
+                .unwrap()
+                .validate()
+        }
+
+        #[test]
+        fn validate_reports_overflow_instead_of_panicking() {
+            for json in [
+                r#"{"card_padding_horizontal": 3000000000}"#,
+                r#"{"rows": 100000, "cut_height": 100000}"#,
+                r#"{"rows": 1, "card_margin_bottom": 4294967295}"#,
+                r#"{"rows": 70000, "cols": 70000}"#,
+                r#"{"container_width": 4294967295, "cut_width": 1, "card_padding_horizontal": 0, "cols": 3}"#,
+            ] {
+                assert!(
+                    matches!(validate_json(json), Err(GridistError::InvalidConfig(_))),
+                    "{} was accepted",
+                    json
+                );
+            }
+        }
+
+        #[test]
+        fn validate_reports_each_problem_once() {
+            let Err(GridistError::InvalidConfig(problems)) = validate_json(r#"{"cols": 3}"#) else {
+                panic!("three default columns were accepted");
+            };
+            assert_eq!(problems.len(), 1, "{:?}", problems);
+            assert!(problems[0].contains("do not fit"), "{:?}", problems);
+        }
+
+        #[test]
+        fn validate_rejects_cuts_too_large_for_gif_frames() {
+            let json = r#"{"container_width": 200000, "cut_width": 65536, "cols": 1}"#;
+            assert!(matches!(
+                validate_json(json),
+                Err(GridistError::InvalidConfig(problems)) if problems.len() == 1
+            ));
+            let json = r#"{"container_width": 200000, "cut_width": 65535, "card_padding_horizontal": 0, "cols": 1}"#;
+            validate_json(json).unwrap();
+        }
+
+        #[test]
+        fn layout_files_reject_unknown_fields() {
+            let error = toml::from_str::<ImageConfig>("cut_widht = 400").unwrap_err();
+            assert!(error.to_string().contains("cut_widht"), "{}", error);
+            assert!(serde_json::from_str::<ImageConfig>(r#"{"rowz": 2}"#).is_err());
+            let config: ImageConfig = toml::from_str("cut_width = 400").unwrap();
+            assert_eq!(config.cut_width, 400);
+        }
+
+        #[test]
+        fn layouts_load_from_json_and_toml_files() {
+            let dir = tempfile::TempDir::new().unwrap();
+            for (name, content) in [
+                ("layout.json", r#"{"rows": 2, "cut_height": 90}"#),
+                ("layout.JSON", r#"{"rows": 2, "cut_height": 90}"#),
+                ("layout.toml", "rows = 2\ncut_height = 90"),
+            ] {
+                let path = dir.path().join(name);
+                fs::write(&path, content).unwrap();
+                let config = ImageConfig::from_file(&path).unwrap();
+                assert_eq!((config.rows, config.cut_height), (2, 90), "{}", name);
+            }
+        }
+
+        #[test]
+        fn validate_accepts_presets() {
+            for name in PRESETS {
+                ImageConfig::preset(name).unwrap().validate().unwrap();
+            }
         }
     }
 }
@@ -125,55 +553,497 @@ pub mod config {
 /// Image and GIF processing functionality
 pub mod cropper {
     use super::*;
+    use crate::animation::{self, AnimationSource, SourcePalette, Timing};
+    use crate::budget::{self, ByteSize, Reduction};
+    use crate::config::{Background, Fit};
+    use crate::dither::{self, Dither};
+    use crate::encode::{self, Encoding};
+    use crate::quantize::{self, PaletteLookup, Quantizer};
+    use crate::saliency::{SaliencyMap, SummedArea};
+    use crate::simd;
+    use gif::DisposalMethod;
     use image::imageops::FilterType;
+    use std::fmt;
+    use std::ops::RangeInclusive;
+    use std::str::FromStr;
+
+    /// An encoded grid tile held in memory
+    #[derive(Debug, Clone)]
+    pub struct Tile {
+        /// Position of the tile in grid order
+        pub index: u32,
+        /// Row of the tile, counted from the top
+        pub row: u32,
+        /// Column of the tile, counted from the left
+        pub col: u32,
+        /// Encoded image data
+        pub bytes: Vec<u8>,
+        /// MIME type of the encoded data
+        pub mime: &'static str,
+    }
+
+    impl Tile {
+        /// Returns the usual file extension for the tile's format
+        pub fn extension(&self) -> &'static str {
+            ImageFormat::from_mime_type(self.mime)
+                .and_then(|format| format.extensions_str().first().copied())
+                .unwrap_or("bin")
+        }
+    }
+
+    /// Default naming template for generated tiles
+    pub const DEFAULT_NAME_TEMPLATE: &str = "{stem}.{index}.{ext}";
+
+    /// Number of animation frames whose saliency places the grid
+    const SMART_CROP_SAMPLES: usize = 8;
+
+    /// Which part of an image the grid is cut from
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub enum Framing {
+        /// Centre the grid on the image
+        #[default]
+        Center,
+        /// Centre the grid on a point, as far as the image allows
+        Focus(FocusPoint),
+        /// Crop the source to a rectangle before fitting it to the grid
+        Region(CropRect),
+        /// Place the grid where the visible tiles keep the most edge detail
+        Smart,
+    }
+
+    impl Framing {
+        /// Checks that a focus point lies inside the image and a region isn't empty
+        pub fn validate(&self) -> GridistResult<()> {
+            match *self {
+                Framing::Focus(FocusPoint { x, y })
+                    if !((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)) =>
+                {
+                    Err(GridistError::InvalidConfig(vec![format!(
+                        "focus must lie between 0 and 1, got {},{}",
+                        x, y
+                    )]))
+                }
+                Framing::Region(rect) if rect.width == 0 || rect.height == 0 => {
+                    Err(GridistError::InvalidConfig(vec![format!(
+                        "crop region must be non-empty, got {}x{}",
+                        rect.width, rect.height
+                    )]))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// A point in relative image coordinates, `0,0` top-left to `1,1` bottom-right
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct FocusPoint {
+        pub x: f32,
+        pub y: f32,
+    }
+
+    impl FromStr for FocusPoint {
+        type Err = GridistError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || {
+                GridistError::InvalidConfig(vec![format!(
+                    "'{}' is not a focus point, use x,y such as 0.5,0.3",
+                    s
+                )])
+            };
+            let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+            Ok(Self {
+                x: x.trim().parse().map_err(|_| invalid())?,
+                y: y.trim().parse().map_err(|_| invalid())?,
+            })
+        }
+    }
+
+    /// A rectangle of the source image in pixels
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CropRect {
+        pub x: u32,
+        pub y: u32,
+        pub width: u32,
+        pub height: u32,
+    }
+
+    impl CropRect {
+        /// Clamps the rectangle to an image of the given size
+        ///
+        /// The result always keeps at least one pixel.
+        pub fn clamped(&self, width: u32, height: u32) -> CropRect {
+            let x = self.x.min(width.saturating_sub(1));
+            let y = self.y.min(height.saturating_sub(1));
+            CropRect {
+                x,
+                y,
+                width: self.width.clamp(1, (width - x).max(1)),
+                height: self.height.clamp(1, (height - y).max(1)),
+            }
+        }
+    }
+
+    impl FromStr for CropRect {
+        type Err = GridistError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || {
+                GridistError::InvalidConfig(vec![format!(
+                    "'{}' is not a crop region, use x,y,width,height in pixels",
+                    s
+                )])
+            };
+            let values = s
+                .split(',')
+                .map(|value| value.trim().parse::<u32>().map_err(|_| invalid()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let [x, y, width, height] = values[..] else {
+                return Err(invalid());
+            };
+            Ok(Self {
+                x,
+                y,
+                width,
+                height,
+            })
+        }
+    }
+
+    /// How the cropper treats the gutters hidden between and around the cards
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct GutterOptions {
+        /// Log how much edge detail each gutter hides
+        pub report: bool,
+        /// Furthest the grid may move, in pixels, to hide less detail; 0 keeps it in place
+        pub max_shift: u32,
+    }
+
+    /// A hidden part of the grid, summed over every card it borders
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Gutter {
+        /// Where the gutter lies, such as "between rows 1 and 2"
+        pub name: String,
+        /// Area of the gutter in pixels
+        pub area: u64,
+        /// Edge detail inside the gutter
+        pub detail: f64,
+    }
+
+    /// Edge detail of a resized image that the grid shows, hides or crops off
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct GutterReport {
+        /// Offsets of the grid inside the resized image
+        pub offsets: (u32, u32),
+        /// Edge detail inside the visible tiles
+        pub visible: f64,
+        /// Area of the visible tiles in pixels
+        pub visible_area: u64,
+        /// Edge detail of the resized image outside the grid
+        pub outside: f64,
+        /// Hidden parts of the grid, from top to bottom
+        pub gutters: Vec<Gutter>,
+    }
+
+    impl GutterReport {
+        /// Returns the edge detail hidden in all gutters
+        pub fn hidden(&self) -> f64 {
+            self.gutters.iter().map(|gutter| gutter.detail).sum()
+        }
+
+        /// Returns the share of the edge detail inside the grid that the gutters hide
+        pub fn hidden_share(&self) -> f64 {
+            let hidden = self.hidden();
+            hidden / (self.visible + hidden).max(f64::EPSILON)
+        }
+    }
+
+    impl fmt::Display for GutterReport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let in_grid = self.visible + self.hidden();
+            // Rounding in the summed-area table leaves noise where there is no detail
+            if in_grid <= (in_grid + self.outside) * 1e-9 {
+                return write!(
+                    f,
+                    "The grid at x={}, y={} holds no edge detail to hide",
+                    self.offsets.0, self.offsets.1
+                );
+            }
+            write!(
+                f,
+                "Gutters at x={}, y={} hide {:.1}% of the edge detail in the grid",
+                self.offsets.0,
+                self.offsets.1,
+                100.0 * self.hidden_share()
+            )?;
+            if self.outside > 0.0 {
+                let total = in_grid + self.outside;
+                write!(
+                    f,
+                    ", and {:.1}% of the image's edge detail lies outside it",
+                    100.0 * self.outside / total
+                )?;
+            }
+
+            // Density relative to the tiles shows where content crosses a gutter
+            let tile_density = self.visible / self.visible_area.max(1) as f64;
+            let mut gutters: Vec<_> = self.gutters.iter().collect();
+            gutters.sort_by(|a, b| b.detail.total_cmp(&a.detail));
+            for gutter in gutters {
+                let density = gutter.detail / gutter.area.max(1) as f64;
+                write!(
+                    f,
+                    "\n  {}: {:.1}% of the grid's detail, {:.2}x the density of the tiles",
+                    gutter.name,
+                    100.0 * gutter.detail / in_grid,
+                    density / tile_density.max(f64::EPSILON)
+                )?;
+            }
+            Ok(())
+        }
+    }
 
     /// Handles the cropping and processing of images into grid layouts
-    #[derive(Default)]
     pub struct ImageCropper {
         config: config::ImageConfig,
+        output_dir: PathBuf,
+        name_template: String,
+        quantizer: Quantizer,
+        dither: Dither,
+        timing: Timing,
+        max_tile_size: Option<ByteSize>,
+        encoding: Encoding,
+        framing: Framing,
+        gutters: GutterOptions,
+        progress: bool,
+    }
+
+    impl Default for ImageCropper {
+        fn default() -> Self {
+            Self {
+                config: config::ImageConfig::default(),
+                output_dir: PathBuf::new(),
+                name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+                quantizer: Quantizer::default(),
+                dither: Dither::default(),
+                timing: Timing::default(),
+                max_tile_size: None,
+                encoding: Encoding::default(),
+                framing: Framing::default(),
+                gutters: GutterOptions::default(),
+                progress: false,
+            }
+        }
     }
 
     impl ImageCropper {
         /// Creates a new ImageCropper with the specified configuration
-        pub fn new(config: config::ImageConfig) -> Self {
-            info!("Creating new ImageCropper with config: {:?}", config);
-            Self { config }
+        ///
+        /// Fails with [`GridistError::InvalidConfig`] if the geometry is inconsistent.
+        pub fn new(config: config::ImageConfig) -> GridistResult<Self> {
+            debug!("Creating new ImageCropper with config: {:?}", config);
+            config.validate()?;
+            Ok(Self {
+                config,
+                ..Self::default()
+            })
+        }
+
+        /// Returns the configuration used by this cropper
+        pub fn config(&self) -> &config::ImageConfig {
+            &self.config
+        }
+
+        /// Sets the directory tiles are written to (the working directory by default)
+        pub fn set_output_dir(&mut self, dir: impl Into<PathBuf>) {
+            self.output_dir = dir.into();
+        }
+
+        /// Sets the template used to name tiles
+        ///
+        /// Supported placeholders are `{stem}`, `{index}`, `{row}`, `{col}` and
+        /// `{ext}`. The extension is appended when `{ext}` is missing. The template
+        /// must contain `{index}`, or both `{row}` and `{col}`, so tiles don't collide.
+        pub fn set_name_template(&mut self, template: impl Into<String>) -> GridistResult<()> {
+            let template = template.into();
+            let unique = template.contains("{index}")
+                || (template.contains("{row}") && template.contains("{col}"));
+            if !unique {
+                return Err(GridistError::InvalidFileName(format!(
+                    "Name template '{}' must contain {{index}} or both {{row}} and {{col}}",
+                    template
+                )));
+            }
+            self.name_template = template;
+            Ok(())
+        }
+
+        /// Sets how the palette of GIF tiles is chosen
+        pub fn set_quantizer(&mut self, quantizer: Quantizer) {
+            self.quantizer = quantizer;
+        }
+
+        /// Sets the dithering applied when mapping GIF tiles to their palette
+        pub fn set_dither(&mut self, dither: Dither) {
+            self.dither = dither;
+        }
+
+        /// Sets the frame rate, trim, frame count and speed of GIF tiles
+        pub fn set_timing(&mut self, timing: Timing) -> GridistResult<()> {
+            timing.validate()?;
+            self.timing = timing;
+            Ok(())
+        }
+
+        /// Sets the largest size a tile may have, shrinking tiles over it
+        pub fn set_max_tile_size(&mut self, size: Option<ByteSize>) {
+            self.max_tile_size = size;
+        }
+
+        /// Returns the encoder settings of still tiles
+        pub fn encoding(&self) -> &Encoding {
+            &self.encoding
+        }
+
+        /// Sets the output format, JPEG quality and PNG compression of still tiles
+        pub fn set_encoding(&mut self, encoding: Encoding) -> GridistResult<()> {
+            encoding.validate()?;
+            self.encoding = encoding;
+            Ok(())
+        }
+
+        /// Sets which part of the image the grid is cut from
+        pub fn set_framing(&mut self, framing: Framing) -> GridistResult<()> {
+            framing.validate()?;
+            self.framing = framing;
+            Ok(())
+        }
+
+        /// Sets whether the gutters are reported and how far the grid may move to avoid them
+        pub fn set_gutters(&mut self, gutters: GutterOptions) {
+            self.gutters = gutters;
+        }
+
+        /// Sets whether cropping draws progress bars on the terminal (off by default)
+        pub fn set_progress(&mut self, progress: bool) {
+            self.progress = progress;
+        }
+
+        /// Returns a draw target for progress bars, hidden unless progress is shown
+        fn progress_target(&self) -> ProgressDrawTarget {
+            if self.progress {
+                ProgressDrawTarget::stderr()
+            } else {
+                ProgressDrawTarget::hidden()
+            }
+        }
+
+        /// Builds the output path of a tile from the naming template
+        fn tile_path(&self, path: &Path, index: u32, extension: &str) -> GridistResult<PathBuf> {
+            let stem = path
+                .file_stem()
+                .ok_or_else(|| GridistError::InvalidFileName("No file stem".to_string()))?
+                .to_str()
+                .ok_or_else(|| {
+                    GridistError::InvalidFileName("Invalid UTF-8 in file stem".to_string())
+                })?;
+            let cols = self.config.cols.max(1);
+
+            let mut filename = self
+                .name_template
+                .replace("{stem}", stem)
+                .replace("{index}", &index.to_string())
+                .replace("{row}", &(index / cols).to_string())
+                .replace("{col}", &(index % cols).to_string());
+            if filename.contains("{ext}") {
+                filename = filename.replace("{ext}", extension);
+            } else {
+                filename = format!("{}.{}", filename, extension);
+            }
+            Ok(self.output_dir.join(filename))
+        }
+
+        /// Creates the output directory if one was set
+        fn ensure_output_dir(&self) -> GridistResult<()> {
+            if !self.output_dir.as_os_str().is_empty() {
+                fs::create_dir_all(&self.output_dir).with_context(|| {
+                    format!(
+                        "Failed to create output directory {}",
+                        self.output_dir.display()
+                    )
+                })?;
+            }
+            Ok(())
         }
 
         /// Calculates the x,y coordinates for a grid segment at the given index
+        ///
+        /// Columns are spread evenly so that the first and last ones are flush
+        /// with the container edges, matching GitHub's pinned layout.
         pub fn get_xy(&self, index: u32) -> (u32, u32) {
-            let is_left = index % 2 == 0;
-            let x = if is_left {
-                self.config.card_padding_horizontal
-            } else {
-                self.config.container_width
+            let cols = self.config.cols.max(1);
+            let col = index % cols;
+            let x = if cols > 1 {
+                let span = self.config.container_width
                     - self.config.cut_width
-                    - self.config.card_padding_horizontal
+                    - 2 * self.config.card_padding_horizontal;
+                self.config.card_padding_horizontal + col * span / (cols - 1)
+            } else {
+                self.config.card_padding_horizontal
             };
-            let index_from_top = index / 2;
+            let index_from_top = index / cols;
             let y = self.config.card_padding_top + index_from_top * self.config.y_offset();
             debug!("Calculated position for index {}: ({}, {})", index, x, y);
             (x, y)
         }
 
-        /// Calculates the dimensions to resize an image while maintaining aspect ratio
+        /// Calculates the dimensions an image is resized to for the fit mode
+        ///
+        /// Cover keeps the aspect ratio and may overflow the grid, stretch
+        /// matches it exactly, and contain and pad stay inside it.
         pub fn calculate_resize_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+            let (grid_width, grid_height) =
+                (self.config.grid_width(), self.config.minimum_height());
+            let contain_scale = f32::min(
+                grid_width as f32 / width as f32,
+                grid_height as f32 / height as f32,
+            );
+            let scale = match self.config.fit {
+                Fit::Cover => return self.cover_dimensions(width, height),
+                Fit::Stretch => return (grid_width, grid_height),
+                Fit::Contain => contain_scale,
+                Fit::Pad => contain_scale.min(1.0),
+            };
+            let resize_width = ((width as f32 * scale).round() as u32).clamp(1, grid_width);
+            let resize_height = ((height as f32 * scale).round() as u32).clamp(1, grid_height);
+            debug!(
+                "Calculated resize dimensions: {}x{} -> {}x{} ({:?})",
+                width, height, resize_width, resize_height, self.config.fit
+            );
+            (resize_width, resize_height)
+        }
+
+        /// Calculates the dimensions that cover the grid while maintaining aspect ratio
+        fn cover_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+            let grid_width = self.config.grid_width();
             let aspect_ratio = width as f32 / height as f32;
-            let target_aspect_ratio =
-                self.config.container_width as f32 / self.config.minimum_height() as f32;
+            let target_aspect_ratio = grid_width as f32 / self.config.minimum_height() as f32;
 
             let (resize_width, resize_height) = if aspect_ratio >= target_aspect_ratio {
                 let scale = f32::max(
-                    self.config.container_width as f32 / width as f32,
+                    grid_width as f32 / width as f32,
                     self.config.minimum_height() as f32 / height as f32,
                 );
                 (
-                    (width as f32 * scale) as u32,
-                    (height as f32 * scale) as u32,
+                    ((width as f32 * scale).round() as u32).max(grid_width),
+                    ((height as f32 * scale).round() as u32).max(self.config.minimum_height()),
                 )
             } else {
-                let scale = self.config.container_width as f32 / width as f32;
-                (self.config.container_width, (height as f32 * scale) as u32)
+                let scale = grid_width as f32 / width as f32;
+                (
+                    grid_width,
+                    ((height as f32 * scale).round() as u32).max(self.config.minimum_height()),
+                )
             };
 
             debug!(
@@ -183,39 +1053,517 @@ pub mod cropper {
             (resize_width, resize_height)
         }
 
-        /// Crops a static image into a grid layout
-        /// Returns paths to the generated grid segments
-        pub fn crop_image(&self, path: &Path) -> GridistResult<Vec<PathBuf>> {
-            info!("Starting image cropping process for: {}", path.display());
-            let image = image::open(path).context("Failed to open image")?;
-            let (width, height) = image.dimensions();
-            info!("Original image dimensions: {}x{}", width, height);
+        /// Calculates the offsets of the grid inside a resized image
+        ///
+        /// The grid is centred, or centred on the focus point and clamped so
+        /// it stays inside the image. Smart framing needs the pixels, see
+        /// [`Self::smart_offsets`], and is centred here.
+        pub fn crop_offsets(&self, resize_width: u32, resize_height: u32) -> (u32, u32) {
+            let overflow_x = resize_width.saturating_sub(self.config.grid_width());
+            let overflow_y = resize_height.saturating_sub(self.config.minimum_height());
+            let Framing::Focus(focus) = self.framing else {
+                return (overflow_x / 2, overflow_y / 2);
+            };
+            let offset = |focus: f32, size: u32, grid: u32, overflow: u32| {
+                let offset = (focus * size as f32 - grid as f32 / 2.0).floor();
+                offset.clamp(0.0, overflow as f32) as u32
+            };
+            (
+                offset(focus.x, resize_width, self.config.grid_width(), overflow_x),
+                offset(
+                    focus.y,
+                    resize_height,
+                    self.config.minimum_height(),
+                    overflow_y,
+                ),
+            )
+        }
 
-            let (resize_width, resize_height) = self.calculate_resize_dimensions(width, height);
-            info!("Resizing image to {}x{}", resize_width, resize_height);
+        /// Finds the offsets where the visible tiles hold the most edge detail
+        ///
+        /// `frames` are resized images of the same size; their saliency is
+        /// summed, so one offset suits the whole animation. Ties go to the
+        /// offset nearest the centre.
+        pub fn smart_offsets(&self, frames: &[&RgbaImage]) -> (u32, u32) {
+            match Self::saliency(frames) {
+                Some((table, size)) => self.smart_search(&table, size),
+                None => (0, 0),
+            }
+        }
 
-            let progress_bar = ProgressBar::new_spinner();
-            progress_bar.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} {msg}")
-                    .unwrap(),
-            );
-            progress_bar.set_message("Resizing image...");
+        /// Measures how much edge detail of resized frames the gutters hide at the given offsets
+        pub fn gutter_report(
+            &self,
+            frames: &[&RgbaImage],
+            offsets: (u32, u32),
+        ) -> Option<GutterReport> {
+            Self::saliency(frames).map(|(table, _)| self.gutter_report_from(&table, offsets))
+        }
 
-            let resized = image.resize(resize_width, resize_height, FilterType::Lanczos3);
-            progress_bar.finish_with_message("Resizing complete");
+        /// Returns whether placing the grid needs the saliency of the resized frames
+        fn uses_saliency(&self) -> bool {
+            self.framing == Framing::Smart || self.gutters.report || self.gutters.max_shift > 0
+        }
 
-            let offset_x =
-                ((resize_width as i32 - self.config.container_width as i32) / 2).max(0) as u32;
-            let offset_y =
-                ((resize_height as i32 - self.config.minimum_height() as i32) / 2).max(0) as u32;
+        /// Places the grid in resized frames for smart framing and the gutter options
+        ///
+        /// Smart framing searches the whole overflow and a gutter shift the
+        /// pixels around `offsets`; the gutter report describes the result.
+        fn place_grid(&self, frames: &[&RgbaImage], offsets: (u32, u32)) -> (u32, u32) {
+            let Some((table, size)) = Self::saliency(frames) else {
+                return offsets;
+            };
+            let mut offsets = if self.framing == Framing::Smart {
+                self.smart_search(&table, size)
+            } else {
+                offsets
+            };
+            if self.gutters.max_shift > 0 {
+                offsets = self.gutter_shift(&table, size, offsets);
+            }
+            if self.gutters.report {
+                info!("{}", self.gutter_report_from(&table, offsets));
+            }
+            offsets
+        }
 
-            info!(
-                "Cropping image into grid with offsets: x={}, y={}",
-                offset_x, offset_y
-            );
+        /// Sums the edge saliency of frames of the same size into a summed-area table
+        fn saliency(frames: &[&RgbaImage]) -> Option<(SummedArea, (u32, u32))> {
+            let (first, rest) = frames.split_first()?;
+            let mut map = SaliencyMap::edges(first);
+            for frame in rest {
+                map.accumulate(&SaliencyMap::edges(frame));
+            }
+            Some((map.summed_area(), map.dimensions()))
+        }
 
-            let progress_bar = ProgressBar::new(6);
+        /// Returns how far the grid can move inside a resized image
+        fn overflow(&self, (width, height): (u32, u32)) -> (u32, u32) {
+            (
+                width.saturating_sub(self.config.grid_width()),
+                height.saturating_sub(self.config.minimum_height()),
+            )
+        }
+
+        /// Sums the edge detail inside the visible tiles for the given offsets
+        fn visible_detail(&self, table: &SummedArea, (offset_x, offset_y): (u32, u32)) -> f64 {
+            (0..self.config.tile_count())
+                .map(|i| {
+                    let (x, y) = self.get_xy(i);
+                    table.sum(
+                        x + offset_x,
+                        y + offset_y,
+                        self.config.cut_width,
+                        self.config.cut_height,
+                    )
+                })
+                .sum()
+        }
+
+        /// Finds the offsets in a range that show the most edge detail
+        ///
+        /// Ties go to the offsets nearest `preferred`.
+        fn best_offsets(
+            &self,
+            table: &SummedArea,
+            xs: RangeInclusive<u32>,
+            ys: RangeInclusive<u32>,
+            preferred: (u32, u32),
+        ) -> ((u32, u32), f64) {
+            let distance = |(x, y): (u32, u32)| x.abs_diff(preferred.0) + y.abs_diff(preferred.1);
+            let mut best = (preferred, self.visible_detail(table, preferred));
+            for offset_y in ys {
+                for offset_x in xs.clone() {
+                    let offset = (offset_x, offset_y);
+                    let score = self.visible_detail(table, offset);
+                    if score > best.1 || (score == best.1 && distance(offset) < distance(best.0)) {
+                        best = (offset, score);
+                    }
+                }
+            }
+            best
+        }
+
+        /// Searches the whole overflow for the offsets that show the most edge detail
+        fn smart_search(&self, table: &SummedArea, size: (u32, u32)) -> (u32, u32) {
+            let (overflow_x, overflow_y) = self.overflow(size);
+            let centre = (overflow_x / 2, overflow_y / 2);
+            if overflow_x == 0 && overflow_y == 0 {
+                return centre;
+            }
+
+            let (best, score) = self.best_offsets(table, 0..=overflow_x, 0..=overflow_y, centre);
+            let total = table.total().max(f64::EPSILON);
+            info!(
+                "Smart crop placed the grid at x={}, y={}, keeping {:.1}% of the edge detail visible (centred: {:.1}%)",
+                best.0,
+                best.1,
+                100.0 * score / total,
+                100.0 * self.visible_detail(table, centre) / total
+            );
+            best
+        }
+
+        /// Moves the grid up to the largest gutter shift if that shows more edge detail
+        fn gutter_shift(
+            &self,
+            table: &SummedArea,
+            size: (u32, u32),
+            offsets: (u32, u32),
+        ) -> (u32, u32) {
+            let (overflow_x, overflow_y) = self.overflow(size);
+            let shift = self.gutters.max_shift;
+            let range = |offset: u32, overflow: u32| {
+                let offset = offset.min(overflow);
+                offset.saturating_sub(shift)..=offset.saturating_add(shift).min(overflow)
+            };
+            if overflow_x == 0 && overflow_y == 0 {
+                info!("Gutter shift has no room: the image fills the grid exactly");
+                return offsets;
+            }
+
+            let before = self.visible_detail(table, offsets);
+            let (best, score) = self.best_offsets(
+                table,
+                range(offsets.0, overflow_x),
+                range(offsets.1, overflow_y),
+                offsets,
+            );
+            if best == offsets {
+                info!(
+                    "Gutter shift kept the grid at x={}, y={}; no offset within {} px shows more detail",
+                    offsets.0, offsets.1, shift
+                );
+            } else {
+                let total = table.total().max(f64::EPSILON);
+                info!(
+                    "Gutter shift moved the grid by {:+}, {:+} px, keeping {:.1}% of the edge detail visible instead of {:.1}%",
+                    best.0 as i64 - offsets.0 as i64,
+                    best.1 as i64 - offsets.1 as i64,
+                    100.0 * score / total,
+                    100.0 * before / total
+                );
+            }
+            best
+        }
+
+        /// Splits the hidden parts of the grid into gutters and sums their edge detail
+        fn gutter_report_from(&self, table: &SummedArea, offsets: (u32, u32)) -> GutterReport {
+            let (offset_x, offset_y) = offsets;
+            let (grid_width, grid_height) =
+                (self.config.grid_width(), self.config.minimum_height());
+            let (cut_width, cut_height) = (self.config.cut_width, self.config.cut_height);
+            let cols = self.config.cols.max(1);
+            let xs: Vec<u32> = (0..cols).map(|col| self.get_xy(col).0).collect();
+            let ys: Vec<u32> = (0..self.config.rows)
+                .map(|row| self.get_xy(row * cols).1)
+                .collect();
+
+            let mut gutters: Vec<Gutter> = Vec::new();
+            let mut add = |name: String, x: u32, y: u32, width: u32, height: u32| {
+                if width == 0 || height == 0 {
+                    return;
+                }
+                let area = u64::from(width) * u64::from(height);
+                let detail = table.sum(x + offset_x, y + offset_y, width, height);
+                match gutters.iter_mut().find(|gutter| gutter.name == name) {
+                    Some(gutter) => {
+                        gutter.area += area;
+                        gutter.detail += detail;
+                    }
+                    None => gutters.push(Gutter { name, area, detail }),
+                }
+            };
+
+            // Full-width bands above, between and below the rows of tiles
+            let mut top = 0;
+            for (row, &y) in ys.iter().enumerate() {
+                let name = if row == 0 {
+                    "above row 1".to_string()
+                } else {
+                    format!("between rows {} and {}", row, row + 1)
+                };
+                add(name, 0, top, grid_width, y.saturating_sub(top));
+                top = y + cut_height;
+            }
+            add(
+                format!("below row {}", ys.len()),
+                0,
+                top,
+                grid_width,
+                grid_height.saturating_sub(top),
+            );
+
+            // Strips beside and between the tiles of each row
+            for &y in &ys {
+                let mut left = 0;
+                for (col, &x) in xs.iter().enumerate() {
+                    let name = if col == 0 {
+                        "left of column 1".to_string()
+                    } else {
+                        format!("between columns {} and {}", col, col + 1)
+                    };
+                    add(name, left, y, x.saturating_sub(left), cut_height);
+                    left = x + cut_width;
+                }
+                add(
+                    format!("right of column {}", xs.len()),
+                    left,
+                    y,
+                    grid_width.saturating_sub(left),
+                    cut_height,
+                );
+            }
+
+            let visible = self.visible_detail(table, offsets);
+            let in_grid = table.sum(offset_x, offset_y, grid_width, grid_height);
+            GutterReport {
+                offsets,
+                visible,
+                visible_area: u64::from(self.config.tile_count())
+                    * u64::from(cut_width)
+                    * u64::from(cut_height),
+                outside: (table.total() - in_grid).max(0.0),
+                gutters,
+            }
+        }
+
+        /// Returns the part of a source image of the given size that is fitted to the grid
+        fn source_region(&self, width: u32, height: u32) -> CropRect {
+            match self.framing {
+                Framing::Region(rect) => rect.clamped(width, height),
+                _ => CropRect {
+                    x: 0,
+                    y: 0,
+                    width,
+                    height,
+                },
+            }
+        }
+
+        /// Resizes an image so the grid fits, returning the image and crop offsets
+        fn resize_for_grid(&self, image: &DynamicImage) -> (DynamicImage, (u32, u32)) {
+            // fit_frame applies the crop region itself
+            if matches!(self.config.fit, Fit::Contain | Fit::Pad) {
+                let (canvas, mut offsets) = self.fit_frame(&image.to_rgba8());
+                if self.uses_saliency() {
+                    offsets = self.place_grid(&[&canvas], offsets);
+                }
+                // Opaque fills keep images without alpha free of it
+                let opaque = match self.config.background {
+                    Background::Color(color) => color[3] == 255,
+                    Background::Blur => true,
+                };
+                let canvas = DynamicImage::ImageRgba8(canvas);
+                return if opaque && !image.color().has_alpha() {
+                    (DynamicImage::ImageRgb8(canvas.to_rgb8()), offsets)
+                } else {
+                    (canvas, offsets)
+                };
+            }
+
+            let region;
+            let image = if let Framing::Region(_) = self.framing {
+                let rect = self.source_region(image.width(), image.height());
+                region = image.crop_imm(rect.x, rect.y, rect.width, rect.height);
+                &region
+            } else {
+                image
+            };
+            let (width, height) = image.dimensions();
+            let (resize_width, resize_height) = self.calculate_resize_dimensions(width, height);
+            debug!("Resizing image to {}x{}", resize_width, resize_height);
+            let resized = image.resize_exact(
+                resize_width,
+                resize_height,
+                self.config.filter.filter_type(),
+            );
+            let mut offsets = self.crop_offsets(resize_width, resize_height);
+            if self.uses_saliency() {
+                offsets = self.place_grid(&[&resized.to_rgba8()], offsets);
+            }
+            (resized, offsets)
+        }
+
+        /// Resizes an RGBA frame for the fit mode, returning it and the crop offsets
+        ///
+        /// Contained and padded frames are centred on a grid-sized canvas
+        /// filled with the background, so their offsets are zero.
+        fn fit_frame(&self, frame: &RgbaImage) -> (RgbaImage, (u32, u32)) {
+            let region;
+            let frame = if let Framing::Region(_) = self.framing {
+                let rect = self.source_region(frame.width(), frame.height());
+                region = image::imageops::crop_imm(frame, rect.x, rect.y, rect.width, rect.height)
+                    .to_image();
+                &region
+            } else {
+                frame
+            };
+
+            let filter = self.config.filter.filter_type();
+            let (width, height) = frame.dimensions();
+            let (resize_width, resize_height) = self.calculate_resize_dimensions(width, height);
+            let resized = image::imageops::resize(frame, resize_width, resize_height, filter);
+            if !matches!(self.config.fit, Fit::Contain | Fit::Pad) {
+                return (resized, self.crop_offsets(resize_width, resize_height));
+            }
+
+            let (grid_width, grid_height) =
+                (self.config.grid_width(), self.config.minimum_height());
+            let mut canvas = match self.config.background {
+                Background::Color(color) => {
+                    RgbaImage::from_pixel(grid_width, grid_height, image::Rgba(color))
+                }
+                Background::Blur => self.blurred_backdrop(frame),
+            };
+            image::imageops::overlay(
+                &mut canvas,
+                &resized,
+                ((grid_width - resize_width) / 2) as i64,
+                ((grid_height - resize_height) / 2) as i64,
+            );
+            (canvas, (0, 0))
+        }
+
+        /// Builds a grid-sized, heavily blurred copy of a frame that covers the grid
+        fn blurred_backdrop(&self, frame: &RgbaImage) -> RgbaImage {
+            // Blurring a small copy and scaling it up is far cheaper than a
+            // wide blur at full size and looks the same
+            const SHRINK: u32 = 8;
+            let (cover_width, cover_height) = self.cover_dimensions(frame.width(), frame.height());
+            let small = image::imageops::resize(
+                frame,
+                (cover_width / SHRINK).max(1),
+                (cover_height / SHRINK).max(1),
+                FilterType::Triangle,
+            );
+            let blurred = image::imageops::fast_blur(&small, 3.0);
+            let cover =
+                image::imageops::resize(&blurred, cover_width, cover_height, FilterType::Triangle);
+            let (offset_x, offset_y) = self.crop_offsets(cover_width, cover_height);
+            image::imageops::crop_imm(
+                &cover,
+                offset_x,
+                offset_y,
+                self.config.grid_width(),
+                self.config.minimum_height(),
+            )
+            .to_image()
+        }
+
+        /// Resizes an image and cuts it into in-memory grid tiles, in grid order
+        pub fn crop_tiles(&self, image: &DynamicImage) -> Vec<RgbaImage> {
+            let (resized, (offset_x, offset_y)) = self.resize_for_grid(image);
+            self.slice_tiles(&resized.to_rgba8(), offset_x, offset_y)
+        }
+
+        /// Places the grid once for a whole animation, if its placement depends on the pixels
+        ///
+        /// Smart framing and the gutter options score evenly spaced frames
+        /// and keep one offset for every frame, so the picture doesn't jump.
+        pub fn animation_offsets(&self, frames: &[RgbaImage]) -> Option<(u32, u32)> {
+            if !self.uses_saliency() || frames.is_empty() {
+                return None;
+            }
+            let step = frames.len().div_ceil(SMART_CROP_SAMPLES);
+            let sampled: Vec<_> = frames
+                .par_iter()
+                .step_by(step)
+                .map(|frame| self.fit_frame(frame))
+                .collect();
+            let fitted: Vec<_> = sampled.iter().map(|(frame, _)| frame).collect();
+            Some(self.place_grid(&fitted, sampled[0].1))
+        }
+
+        /// Resizes an animation frame and cuts it into grid tiles, in grid order
+        ///
+        /// `offsets` comes from [`Self::animation_offsets`].
+        pub fn crop_frame(&self, frame: &RgbaImage, offsets: Option<(u32, u32)>) -> Vec<RgbaImage> {
+            let (fitted, fitted_offsets) = self.fit_frame(frame);
+            let (offset_x, offset_y) = offsets.unwrap_or(fitted_offsets);
+            self.slice_tiles(&fitted, offset_x, offset_y)
+        }
+
+        /// Cuts every grid tile out of an already resized image, in grid order
+        fn slice_tiles(&self, resized: &RgbaImage, offset_x: u32, offset_y: u32) -> Vec<RgbaImage> {
+            (0..self.config.tile_count())
+                .map(|i| {
+                    let (base_x, base_y) = self.get_xy(i);
+                    image::imageops::crop_imm(
+                        resized,
+                        base_x + offset_x,
+                        base_y + offset_y,
+                        self.config.cut_width,
+                        self.config.cut_height,
+                    )
+                    .to_image()
+                })
+                .collect()
+        }
+
+        /// Crops a static image file into a grid layout
+        /// Returns paths to the generated grid segments
+        pub fn crop_image(&self, path: &Path) -> GridistResult<Vec<PathBuf>> {
+            info!("Starting image cropping process for: {}", path.display());
+            let bytes = fs::read(path)
+                .with_context(|| format!("Failed to open image: {}", path.display()))?;
+            let format = detect_format(&bytes)?;
+            let image = image::load_from_memory_with_format(&bytes, format)
+                .context("Failed to open image")?;
+            let tiles = self.crop_dynamic_image(&image, format)?;
+            let extension = match self.encoding.format {
+                Some(output) => output.image_format().extensions_str()[0].to_string(),
+                None => tile_extension(path, format),
+            };
+            self.write_tiles(path, &tiles, &extension)
+        }
+
+        /// Crops an encoded static image into grid tiles, keeping its format
+        pub fn crop_image_bytes(&self, bytes: &[u8]) -> GridistResult<Vec<Tile>> {
+            let format = detect_format(bytes)?;
+            let image = image::load_from_memory_with_format(bytes, format)?;
+            self.crop_dynamic_image(&image, format)
+        }
+
+        /// Crops a decoded image read as `format` into grid tiles
+        ///
+        /// Tiles keep `format` unless the encoder settings choose another.
+        pub fn crop_dynamic_image(
+            &self,
+            image: &DynamicImage,
+            format: ImageFormat,
+        ) -> GridistResult<Vec<Tile>> {
+            let (width, height) = image.dimensions();
+            debug!("Original image dimensions: {}x{}", width, height);
+
+            let progress_bar = ProgressBar::with_draw_target(None, self.progress_target());
+            progress_bar.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} {msg}")
+                    .unwrap(),
+            );
+            progress_bar.set_message("Resizing image...");
+
+            let (resized, (offset_x, offset_y)) = self.resize_for_grid(image);
+            progress_bar.finish_with_message("Resizing complete");
+
+            debug!(
+                "Cropping image into grid with offsets: x={}, y={}",
+                offset_x, offset_y
+            );
+
+            let format = self.encoding.output_format(format);
+            // Still GIF tiles choose their palette like animated ones
+            let gif_lookup = (format == ImageFormat::Gif && self.quantizer != Quantizer::PerTile)
+                .then(|| {
+                    let rgba = resized.to_rgba8();
+                    let visible = self.slice_tiles(&rgba, offset_x, offset_y);
+                    PaletteLookup::quantized(&visible.iter().collect::<Vec<_>>(), 256)
+                });
+
+            let tile_count = self.config.tile_count();
+            let progress_bar =
+                ProgressBar::with_draw_target(Some(tile_count as u64), self.progress_target());
             progress_bar.set_style(
                 ProgressStyle::default_bar()
                     .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
@@ -223,53 +1571,40 @@ pub mod cropper {
                     .progress_chars("#>-"),
             );
 
-            let output_files: Vec<_> = (0..6)
+            let tiles: Vec<_> = (0..tile_count)
                 .into_par_iter()
-                .map(|i| -> GridistResult<PathBuf> {
-                    let result = (|| -> GridistResult<PathBuf> {
-                        debug!("Processing grid segment {}/6", i + 1);
-                        let filename = format!(
-                            "{}.{}.{}",
-                            path.file_stem()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "No file stem".to_string()
-                                ))?
-                                .to_str()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "Invalid UTF-8 in file stem".to_string()
-                                ))?,
-                            i,
-                            path.extension()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "No file extension".to_string()
-                                ))?
-                                .to_str()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "Invalid UTF-8 in extension".to_string()
-                                ))?
-                        );
-                        let output_path = PathBuf::from(&filename);
-                        debug!("Creating output file: {}", output_path.display());
-
+                .map(|i| -> GridistResult<Tile> {
+                    let result = (|| -> GridistResult<Tile> {
+                        debug!("Processing grid segment {}/{}", i + 1, tile_count);
                         let (base_x, base_y) = self.get_xy(i);
                         let x = base_x + offset_x;
                         let y = base_y + offset_y;
 
                         let cropped =
                             resized.crop_imm(x, y, self.config.cut_width, self.config.cut_height);
-                        cropped.save(&output_path).with_context(|| {
-                            format!("Failed to save cropped image {}", output_path.display())
-                        })?;
-
-                        debug!(
-                            "Successfully saved grid segment {} to {}",
-                            i + 1,
-                            output_path.display()
-                        );
-                        Ok(output_path)
+                        let bytes = if format == ImageFormat::Gif {
+                            let rgba = cropped.to_rgba8();
+                            let lookup = match &gif_lookup {
+                                Some(lookup) => Cow::Borrowed(lookup),
+                                None => Cow::Owned(PaletteLookup::quantized(&[&rgba], 256)),
+                            };
+                            let bytes = self.encode_gif_tile(&[0], &[&rgba], &lookup, (x, y))?;
+                            self.fit_gif_tile(i, bytes, &[0], &[&rgba], &lookup, (x, y))?
+                        } else {
+                            let bytes = encode::encode_image(&cropped, format, &self.encoding)?;
+                            self.fit_static_tile(i, bytes, &cropped, format)?
+                        };
+
+                        debug!("Encoded grid segment {} ({} bytes)", i + 1, bytes.len());
+                        Ok(self.tile(i, bytes, format))
                     })();
                     if let Err(ref e) = result {
-                        error!("Failed to process grid segment {}/6: {}", i + 1, e);
+                        error!(
+                            "Failed to process grid segment {}/{}: {}",
+                            i + 1,
+                            tile_count,
+                            e
+                        );
                     }
                     progress_bar.inc(1);
                     result
@@ -277,183 +1612,165 @@ pub mod cropper {
                 .collect::<Result<Vec<_>, _>>()?;
 
             progress_bar.finish_with_message("Grid creation complete");
-            info!("Successfully created {} grid segments", output_files.len());
-            Ok(output_files)
+            debug!("Successfully created {} grid segments", tiles.len());
+            Ok(tiles)
         }
 
-        /// Crops an animated GIF into a grid layout, maintaining animation
+        /// Crops an animated GIF file into a grid layout, maintaining animation
         /// Returns paths to the generated grid segments
         pub fn crop_gif(&self, path: &Path) -> GridistResult<Vec<PathBuf>> {
-            info!("Reading GIF file: {}", path.display());
-            let multi_progress = MultiProgress::new();
+            self.crop_animation(path)
+        }
+
+        /// Crops an encoded animated GIF into animated grid tiles
+        pub fn crop_gif_bytes(&self, bytes: &[u8]) -> GridistResult<Vec<Tile>> {
+            self.crop_animation_bytes(bytes, ImageFormat::Gif)
+        }
+
+        /// Crops an animated GIF, APNG or WebP file into animated GIF tiles
+        ///
+        /// The format is detected from the file contents.
+        pub fn crop_animation(&self, path: &Path) -> GridistResult<Vec<PathBuf>> {
+            info!("Reading animation file: {}", path.display());
+            let bytes = fs::read(path)
+                .with_context(|| format!("Failed to open animation file: {}", path.display()))?;
+            let format = detect_format(&bytes)?;
+            let tiles = self.crop_animation_bytes(&bytes, format)?;
+            self.write_tiles(path, &tiles, "gif")
+        }
+
+        /// Crops an encoded animation into animated GIF tiles
+        ///
+        /// Fails when `format` has no animation support or the data holds a
+        /// still image.
+        pub fn crop_animation_bytes(
+            &self,
+            bytes: &[u8],
+            format: ImageFormat,
+        ) -> GridistResult<Vec<Tile>> {
+            let mut source = animation::open(bytes, format)?.ok_or_else(|| {
+                anyhow::anyhow!("{} data is not animated", format.extensions_str()[0])
+            })?;
+            self.crop_animation_source(source.as_mut())
+        }
+
+        /// Crops the frames of an animation source into animated GIF tiles
+        pub fn crop_animation_source(
+            &self,
+            source: &mut dyn AnimationSource,
+        ) -> GridistResult<Vec<Tile>> {
+            let multi_progress = MultiProgress::with_draw_target(self.progress_target());
             let spinner = multi_progress.add(ProgressBar::new_spinner());
             spinner.set_style(
                 ProgressStyle::default_spinner()
                     .template("{spinner:.green} {msg}")
                     .unwrap(),
             );
-            spinner.set_message("Reading GIF frames...");
+            spinner.set_message("Reading frames...");
 
-            let file = File::open(path)
-                .with_context(|| format!("Failed to open GIF file: {}", path.display()))?;
-            let mut decoder = Decoder::new(file).with_context(|| "Failed to create GIF decoder")?;
+            // Frames may only cover part of the canvas, so sources composite
+            // them in order before they are resized in parallel
             let mut frames = Vec::new();
-            let global_palette = decoder.global_palette().map(|p| p.to_vec());
-
-            while let Some(frame) = decoder
-                .read_next_frame()
-                .with_context(|| "Failed to read GIF frame")?
-            {
-                frames.push(frame.clone());
+            while let Some(frame) = source.next_frame()? {
+                frames.push(frame);
             }
             spinner.finish_with_message(format!("Read {} frames", frames.len()));
 
-            let orig_width = decoder.width() as f32;
-            let orig_height = decoder.height() as f32;
-
+            let (source_width, source_height) = source.dimensions();
+            let region = self.source_region(source_width, source_height);
+            let (orig_width, orig_height) = (region.width, region.height);
             let (target_width, target_height) =
-                self.calculate_resize_dimensions(orig_width as u32, orig_height as u32);
-
-            let offset_x =
-                ((target_width as i32 - self.config.container_width as i32) / 2).max(0) as u32;
-            let offset_y =
-                ((target_height as i32 - self.config.minimum_height() as i32) / 2).max(0) as u32;
+                self.calculate_resize_dimensions(orig_width, orig_height);
 
-            let default_palette = self.create_default_palette();
-            let encoder_palette = global_palette
-                .as_ref()
-                .or_else(|| frames.first().and_then(|f| f.palette.as_ref()))
-                .map_or(&default_palette, |p| p);
+            let (mut offset_x, mut offset_y) = self.crop_offsets(target_width, target_height);
 
-            let palette_lookup = self.create_optimized_palette_lookup(encoder_palette);
+            // Frames are picked after compositing, since every frame may
+            // contribute to the ones drawn after it
+            let delays: Vec<_> = frames.iter().map(|frame| frame.delay).collect();
+            let mut schedule = self.timing.schedule(&delays)?.into_iter().peekable();
+            let source_count = frames.len();
+            let (delays, canvases): (Vec<_>, Vec<_>) = frames
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, frame)| {
+                    let (_, delay) = schedule.next_if(|&(index, _)| index == i)?;
+                    Some((delay, frame.image))
+                })
+                .unzip();
+            if delays.len() != source_count {
+                info!("Kept {} of {} frames", delays.len(), source_count);
+            }
 
-            info!("Creating grid from GIF with {} frames", frames.len());
-            let grid_progress = multi_progress.add(ProgressBar::new(6));
-            grid_progress.set_style(
+            debug!(
+                "Resizing {} frames to {}x{}",
+                delays.len(),
+                target_width,
+                target_height
+            );
+            let frame_progress = multi_progress.add(ProgressBar::new(delays.len() as u64));
+            frame_progress.set_style(
                 ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} Grid {msg}")
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} Frames")
                     .unwrap()
                     .progress_chars("#>-"),
             );
 
-            let frame_progress =
-                Arc::new(multi_progress.add(ProgressBar::new(frames.len() as u64 * 6)));
-            frame_progress.set_style(
+            let placed_offsets = self.animation_offsets(&canvases);
+            if let Some(offsets) = placed_offsets {
+                (offset_x, offset_y) = offsets;
+            }
+
+            // Every frame is resized once, then sliced into all tiles
+            let frame_tiles: Vec<Vec<RgbaImage>> = canvases
+                .into_par_iter()
+                .map(|canvas| {
+                    frame_progress.inc(1);
+                    self.crop_frame(&canvas, placed_offsets)
+                })
+                .collect();
+            frame_progress.finish();
+
+            debug!("Creating grid from animation with {} frames", delays.len());
+            let tile_count = self.config.tile_count();
+            let grid_progress = multi_progress.add(ProgressBar::new(tile_count as u64));
+            grid_progress.set_style(
                 ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} Frames")
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} Grid {msg}")
                     .unwrap()
                     .progress_chars("#>-"),
             );
 
-            let output_files: Vec<_> = (0..6)
+            let source_palette = match self.quantizer {
+                Quantizer::Source => source.shared_palette(),
+                _ => None,
+            };
+            let all_images: Vec<_> = frame_tiles.iter().flatten().collect();
+            let shared_lookup = match (source_palette, self.quantizer) {
+                (Some(palette), _) => Some(self.source_lookup(palette, &all_images)),
+                (None, Quantizer::PerTile) => None,
+                (None, _) => {
+                    debug!("Building a global palette with median cut");
+                    Some(PaletteLookup::quantized(&all_images, 256))
+                }
+            };
+
+            let tiles: Vec<_> = (0..tile_count)
                 .into_par_iter()
-                .map(|i| -> GridistResult<PathBuf> {
-                    let result = (|| -> GridistResult<PathBuf> {
+                .map(|i| -> GridistResult<Tile> {
+                    let result = (|| -> GridistResult<Tile> {
+                        let images: Vec<_> =
+                            frame_tiles.iter().map(|tiles| &tiles[i as usize]).collect();
+                        let lookup = match &shared_lookup {
+                            Some(lookup) => Cow::Borrowed(lookup),
+                            None => Cow::Owned(PaletteLookup::quantized(&images, 256)),
+                        };
                         let (base_x, base_y) = self.get_xy(i);
-                        let x = base_x + offset_x;
-                        let y = base_y + offset_y;
-
-                        let filename = format!(
-                            "{}.{}.gif",
-                            path.file_stem()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "No file stem".to_string()
-                                ))?
-                                .to_str()
-                                .ok_or_else(|| GridistError::InvalidFileName(
-                                    "Invalid UTF-8 in file stem".to_string()
-                                ))?,
-                            i
-                        );
-                        let output_path = PathBuf::from(&filename);
-                        let output = File::create(&output_path).with_context(|| {
-                            format!("Failed to create output file: {}", output_path.display())
-                        })?;
-
-                        let mut encoder = Encoder::new(
-                            output,
-                            self.config.cut_width as u16,
-                            self.config.cut_height as u16,
-                            encoder_palette,
-                        )
-                        .with_context(|| "Failed to create GIF encoder")?;
-
-                        encoder
-                            .set_repeat(Repeat::Infinite)
-                            .with_context(|| "Failed to set GIF repeat mode")?;
-
-                        let frame_progress = Arc::clone(&frame_progress);
-                        let processed_frames: Vec<_> = frames
-                            .par_iter()
-                            .map(|frame| -> GridistResult<Frame> {
-                                let result = (|| -> GridistResult<Frame> {
-                                    let mut resized_frame = Frame {
-                                        delay: frame.delay,
-                                        dispose: frame.dispose,
-                                        transparent: frame.transparent,
-                                        needs_user_input: frame.needs_user_input,
-                                        top: 0,
-                                        left: 0,
-                                        width: self.config.cut_width as u16,
-                                        height: self.config.cut_height as u16,
-                                        ..Default::default()
-                                    };
-
-                                    let rgba_buffer =
-                                        self.convert_to_rgba_optimized(frame, encoder_palette);
-                                    let image = RgbaImage::from_raw(
-                                        frame.width as u32,
-                                        frame.height as u32,
-                                        rgba_buffer,
-                                    )
-                                    .ok_or_else(|| {
-                                        GridistError::ImageProcessingError(
-                                            image::ImageError::Limits(
-                                                image::error::LimitError::from_kind(
-                                                    image::error::LimitErrorKind::DimensionError,
-                                                ),
-                                            ),
-                                        )
-                                    })?;
-
-                                    let resized = image::imageops::resize(
-                                        &image,
-                                        target_width,
-                                        target_height,
-                                        FilterType::Lanczos3,
-                                    );
-
-                                    let cropped = image::imageops::crop_imm(
-                                        &resized,
-                                        x,
-                                        y,
-                                        self.config.cut_width,
-                                        self.config.cut_height,
-                                    )
-                                    .to_image();
-
-                                    let cropped_rgba = cropped.as_raw();
-                                    let indexed_buffer = self.convert_to_indexed_optimized(
-                                        cropped_rgba,
-                                        &palette_lookup,
-                                        frame.transparent.unwrap_or(0),
-                                    );
-
-                                    resized_frame.buffer = Cow::Owned(indexed_buffer);
-                                    Ok(resized_frame)
-                                })();
-                                frame_progress.inc(1);
-                                result
-                            })
-                            .collect::<Result<Vec<_>, _>>()?;
-
-                        for frame in processed_frames {
-                            encoder
-                                .write_frame(&frame)
-                                .map_err(GridistError::GifEncodingError)?;
-                        }
-
-                        Ok(output_path)
+                        let origin = (base_x + offset_x, base_y + offset_y);
+                        let bytes =
+                            self.encode_gif_tile(&delays, &images, lookup.as_ref(), origin)?;
+                        let bytes =
+                            self.fit_gif_tile(i, bytes, &delays, &images, &lookup, origin)?;
+                        Ok(self.tile(i, bytes, ImageFormat::Gif))
                     })();
                     grid_progress.inc(1);
                     result
@@ -461,175 +1778,3244 @@ pub mod cropper {
                 .collect::<Result<Vec<_>, _>>()?;
 
             grid_progress.finish_with_message("complete");
-            frame_progress.finish();
-            Ok(output_files)
+            Ok(tiles)
         }
 
-        /// Creates a default color palette for GIF processing
-        fn create_default_palette(&self) -> Vec<u8> {
-            let mut palette = Vec::with_capacity(768);
+        /// Encodes the frames of one tile as an animated GIF using `lookup`
+        ///
+        /// `origin` is the tile's position in the resized image, which keeps
+        /// ordered dithering patterns continuous across neighbouring tiles.
+        /// Consecutive identical frames are merged into one with their delays
+        /// summed.
+        fn encode_gif_tile(
+            &self,
+            delays: &[u16],
+            images: &[&RgbaImage],
+            lookup: &PaletteLookup,
+            origin: (u32, u32),
+        ) -> GridistResult<Vec<u8>> {
+            let mut bytes = Vec::new();
+            let mut encoder = Encoder::new(
+                &mut bytes,
+                self.config.cut_width as u16,
+                self.config.cut_height as u16,
+                lookup.palette(),
+            )
+            .with_context(|| "Failed to create GIF encoder")?;
 
-            let base_colors = [
-                (255, 0, 0),     // Red
-                (0, 255, 0),     // Green
-                (0, 0, 255),     // Blue
-                (255, 255, 0),   // Yellow
-                (255, 0, 255),   // Magenta
-                (0, 255, 255),   // Cyan
-                (255, 255, 255), // White
-                (0, 0, 0),       // Black
-            ];
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .with_context(|| "Failed to set GIF repeat mode")?;
 
-            for &(r, g, b) in &base_colors {
-                palette.push(r);
-                palette.push(g);
-                palette.push(b);
-            }
+            let indexed: Vec<_> = images
+                .par_iter()
+                .map(|image| match self.dither {
+                    Dither::None => self.convert_to_indexed_optimized(image.as_raw(), lookup),
+                    method => dither::dither_image(image, origin, method, lookup),
+                })
+                .collect();
 
-            for i in 0..31 {
-                palette.push((i * 8) as u8);
-                palette.push((i * 8) as u8);
-                palette.push((i * 8) as u8);
+            // Without transparency, each frame only stores the rectangle that
+            // changed and keeps the rest of the previous one. Transparent
+            // pixels can't erase what a kept frame left behind, so tiles with
+            // transparency use full frames cleared after each other.
+            let (width, height) = (
+                self.config.cut_width as usize,
+                self.config.cut_height as usize,
+            );
+            let delta = lookup.transparent().is_none();
+            let mut encoded: Vec<Frame> = Vec::with_capacity(delays.len());
+            let mut previous: Option<&[u8]> = None;
+            for (&delay, buffer) in delays.iter().zip(&indexed) {
+                if previous == Some(buffer.as_slice()) {
+                    // Identical to the frame shown before, so it only extends it
+                    if let Some(last) = encoded.last_mut() {
+                        last.delay = last.delay.saturating_add(delay);
+                    }
+                    continue;
+                }
+
+                let (left, top, rect_width, rect_height) = match previous {
+                    Some(previous) if delta => changed_rect(previous, buffer, width),
+                    _ => (0, 0, width, height),
+                };
+                let rect_buffer = buffer
+                    .chunks_exact(width)
+                    .skip(top)
+                    .take(rect_height)
+                    .flat_map(|row| &row[left..left + rect_width])
+                    .copied()
+                    .collect::<Vec<_>>();
+                encoded.push(Frame {
+                    delay,
+                    dispose: if delta {
+                        DisposalMethod::Keep
+                    } else {
+                        DisposalMethod::Background
+                    },
+                    // Every frame shares the tile palette and its slot
+                    transparent: lookup.transparent(),
+                    left: left as u16,
+                    top: top as u16,
+                    width: rect_width as u16,
+                    height: rect_height as u16,
+                    buffer: Cow::Owned(rect_buffer),
+                    ..Default::default()
+                });
+                previous = Some(buffer);
             }
+            debug!(
+                "Encoding {} of {} frames after merging identical ones",
+                encoded.len(),
+                delays.len()
+            );
 
-            while palette.len() < 768 {
-                palette.push(0);
-                palette.push(0);
-                palette.push(0);
+            for frame in encoded {
+                encoder
+                    .write_frame(&frame)
+                    .map_err(GridistError::GifEncodingError)?;
             }
+            drop(encoder);
+            Ok(bytes)
+        }
 
-            palette
+        /// Re-encodes a GIF tile that is over the size budget and reports
+        /// what was changed
+        fn fit_gif_tile(
+            &self,
+            index: u32,
+            bytes: Vec<u8>,
+            delays: &[u16],
+            images: &[&RgbaImage],
+            lookup: &PaletteLookup,
+            origin: (u32, u32),
+        ) -> GridistResult<Vec<u8>> {
+            let Some(budget) = self.max_tile_size.filter(|b| bytes.len() as u64 > b.0) else {
+                return Ok(bytes);
+            };
+            let original = bytes.len();
+            let (bytes, changes) =
+                self.reduce_gif_tile(bytes, budget, delays, images, lookup, origin)?;
+            budget::report(index, original, bytes.len(), budget, &changes);
+            Ok(bytes)
         }
 
-        /// Creates an optimized lookup table for palette colors
-        fn create_optimized_palette_lookup(&self, palette: &[u8]) -> Vec<(u8, [u8; 3])> {
-            let mut lookup = Vec::with_capacity(palette.len() / 3);
-            for (idx, colors) in palette.chunks(3).enumerate() {
-                if colors.len() < 3 {
-                    continue;
+        /// Re-encodes a GIF tile with fewer colours and frames until it fits
+        /// `budget`, returning the new bytes and what was changed
+        ///
+        /// Colours are halved down to 64 first, then frames are dropped evenly
+        /// in proportion to the overshoot, then colours are halved again.
+        fn reduce_gif_tile(
+            &self,
+            bytes: Vec<u8>,
+            budget: ByteSize,
+            delays: &[u16],
+            images: &[&RgbaImage],
+            lookup: &PaletteLookup,
+            origin: (u32, u32),
+        ) -> GridistResult<(Vec<u8>, Vec<Reduction>)> {
+            let source_colors = lookup.palette().len() / 3;
+            let (mut bytes, mut colors, mut kept) = (bytes, source_colors, delays.len());
+            while bytes.len() as u64 > budget.0 {
+                if colors > 64 {
+                    colors /= 2;
+                } else if kept > 1 {
+                    let ratio = budget.0 as f64 / bytes.len() as f64;
+                    kept = ((kept as f64 * ratio * 0.95) as usize).clamp(1, kept - 1);
+                } else if colors > 2 {
+                    colors /= 2;
+                } else {
+                    break;
                 }
-                lookup.push((idx as u8, [colors[0], colors[1], colors[2]]));
+
+                // Still tiles and tiles that keep every frame need no rescheduling
+                let (kept_delays, kept_images): (Vec<_>, Vec<_>) = if kept < delays.len() {
+                    let timing = Timing {
+                        max_frames: Some(kept),
+                        ..Timing::default()
+                    };
+                    timing
+                        .schedule(delays)?
+                        .into_iter()
+                        .map(|(i, delay)| (delay, images[i]))
+                        .unzip()
+                } else {
+                    delays.iter().copied().zip(images.iter().copied()).unzip()
+                };
+                let lookup = PaletteLookup::quantized(&kept_images, colors);
+                bytes = self.encode_gif_tile(&kept_delays, &kept_images, &lookup, origin)?;
             }
-            lookup
-        }
 
-        /// Creates a KD-tree for efficient color matching
-        fn create_palette_kdtree(&self, palette: &[u8]) -> KdTree<f32, u8, [f32; 3]> {
-            let mut kdtree = KdTree::new(3);
-            for (idx, colors) in palette.chunks(3).enumerate() {
-                if colors.len() < 3 {
-                    continue;
-                }
-                kdtree
-                    .add(
-                        [colors[0] as f32, colors[1] as f32, colors[2] as f32],
-                        idx as u8,
-                    )
-                    .unwrap();
+            let mut changes = Vec::new();
+            if colors < source_colors {
+                changes.push(Reduction::Colors(colors));
+            }
+            if kept < delays.len() {
+                changes.push(Reduction::Frames {
+                    kept,
+                    total: delays.len(),
+                });
             }
-            kdtree
+            Ok((bytes, changes))
         }
 
-        /// Converts RGBA pixels to indexed colors using the palette
-        fn convert_to_indexed_optimized(
+        /// Re-encodes a static tile that is over the size budget and reports
+        /// what was changed
+        fn fit_static_tile(
             &self,
-            rgba: &[u8],
-            palette_lookup: &[(u8, [u8; 3])],
-            transparent: u8,
-        ) -> Vec<u8> {
-            let kdtree = self.create_palette_kdtree(
-                &palette_lookup
-                    .iter()
-                    .flat_map(|(_, colors)| colors.iter().copied())
-                    .collect::<Vec<_>>(),
-            );
-            let result = Arc::new(Mutex::new(Vec::with_capacity(rgba.len() / 4)));
-
-            let chunk_size = 8; // Process 8 pixels at a time
-            rgba.par_chunks(4 * chunk_size).for_each(|chunk| {
-                let mut local_result = Vec::with_capacity(chunk_size);
+            index: u32,
+            bytes: Vec<u8>,
+            image: &DynamicImage,
+            format: ImageFormat,
+        ) -> GridistResult<Vec<u8>> {
+            let Some(budget) = self.max_tile_size.filter(|b| bytes.len() as u64 > b.0) else {
+                return Ok(bytes);
+            };
+            let original = bytes.len();
+            let (bytes, change) = self.reduce_static_tile(bytes, budget, image, format)?;
+            budget::report(index, original, bytes.len(), budget, change.as_slice());
+            Ok(bytes)
+        }
 
-                for pixel in chunk.chunks(4) {
-                    if pixel[3] < 128 {
-                        local_result.push(transparent);
-                        continue;
+        /// Re-encodes a static tile until it fits `budget`, returning the new
+        /// bytes and what was changed
+        ///
+        /// JPEG tiles get a lower quality, other formats fewer colours. Like
+        /// GIF tiles, reduced pixels are either opaque or fully transparent,
+        /// and PNG stores them with a palette.
+        fn reduce_static_tile(
+            &self,
+            bytes: Vec<u8>,
+            budget: ByteSize,
+            image: &DynamicImage,
+            format: ImageFormat,
+        ) -> GridistResult<(Vec<u8>, Option<Reduction>)> {
+            let mut bytes = bytes;
+            let mut change = None;
+            if format == ImageFormat::Jpeg {
+                let qualities = [65, 55, 45, 35, 25, 15];
+                for quality in qualities
+                    .into_iter()
+                    .filter(|&q| q < self.encoding.jpeg_quality)
+                {
+                    let encoding = Encoding {
+                        jpeg_quality: quality,
+                        ..self.encoding
+                    };
+                    bytes = encode::encode_image(image, format, &encoding)?;
+                    change = Some(Reduction::Quality(quality));
+                    if bytes.len() as u64 <= budget.0 {
+                        break;
                     }
-
-                    let nearest = kdtree
-                        .nearest(
-                            &[pixel[0] as f32, pixel[1] as f32, pixel[2] as f32],
-                            1,
-                            &squared_euclidean,
-                        )
-                        .unwrap();
-
-                    local_result.push(*nearest[0].1);
                 }
-
-                let mut result = result.lock().unwrap();
-                result.extend(local_result);
-            });
-
-            Arc::try_unwrap(result).unwrap().into_inner().unwrap()
-        }
-
-        /// Converts indexed colors to RGBA using the palette
-        fn convert_to_rgba_optimized(&self, frame: &Frame, palette: &[u8]) -> Vec<u8> {
-            let buffer_size = frame.buffer.len() * 4;
-            let mut rgba = Vec::with_capacity(buffer_size);
-            let transparent = frame.transparent;
-
-            let chunk_size = 8; // Process 8 pixels at a time
-            let chunks = frame.buffer.chunks_exact(chunk_size);
-            let remainder = chunks.remainder();
-
-            // Process main chunks
-            for chunk in chunks {
-                let mut local_rgba = Vec::with_capacity(chunk_size * 4);
-                for &pixel in chunk {
-                    let palette_idx = (pixel as usize) * 3;
-                    let alpha = if transparent.map(|t| t == pixel).unwrap_or(false) {
-                        0u8
+            } else {
+                let rgba = image.to_rgba8();
+                // The optimised PNG encoder writes an indexed PNG for them
+                let encoding = Encoding {
+                    optimize: true,
+                    ..self.encoding
+                };
+                for colors in [256, 128, 64, 32, 16, 8, 4, 2] {
+                    let lookup = PaletteLookup::quantized(&[&rgba], colors);
+                    let mut reduced = rgba.clone();
+                    for pixel in reduced.pixels_mut() {
+                        let index = lookup.index(pixel.0);
+                        pixel.0 = if Some(index) == lookup.transparent() {
+                            [0; 4]
+                        } else {
+                            let color = &lookup.palette()[index as usize * 3..][..3];
+                            [color[0], color[1], color[2], 255]
+                        };
+                    }
+                    let reduced = if image.color().has_alpha() {
+                        DynamicImage::ImageRgba8(reduced)
                     } else {
-                        255u8
+                        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(reduced).to_rgb8())
                     };
-
-                    if palette_idx + 2 < palette.len() {
-                        local_rgba.extend_from_slice(&[
-                            palette[palette_idx],
-                            palette[palette_idx + 1],
-                            palette[palette_idx + 2],
-                            alpha,
-                        ]);
-                    } else {
-                        local_rgba.extend_from_slice(&[0, 0, 0, alpha]);
+                    bytes = encode::encode_image(&reduced, format, &encoding)?;
+                    change = Some(Reduction::Colors(colors));
+                    if bytes.len() as u64 <= budget.0 {
+                        break;
                     }
                 }
-                rgba.extend(local_rgba);
             }
+            Ok((bytes, change))
+        }
 
-            // Process remaining pixels
-            for &pixel in remainder {
-                let palette_idx = (pixel as usize) * 3;
-                let alpha = if transparent.map(|t| t == pixel).unwrap_or(false) {
-                    0
-                } else {
-                    255
-                };
+        /// Wraps encoded tile data with its grid position
+        fn tile(&self, index: u32, bytes: Vec<u8>, format: ImageFormat) -> Tile {
+            let cols = self.config.cols.max(1);
+            Tile {
+                index,
+                row: index / cols,
+                col: index % cols,
+                bytes,
+                mime: format.to_mime_type(),
+            }
+        }
 
-                if palette_idx + 2 < palette.len() {
-                    rgba.extend_from_slice(&[
-                        palette[palette_idx],
-                        palette[palette_idx + 1],
-                        palette[palette_idx + 2],
-                        alpha,
-                    ]);
-                } else {
-                    rgba.extend_from_slice(&[0, 0, 0, alpha]);
-                }
+        /// Writes tiles to the output directory using the naming template
+        fn write_tiles(
+            &self,
+            path: &Path,
+            tiles: &[Tile],
+            extension: &str,
+        ) -> GridistResult<Vec<PathBuf>> {
+            self.ensure_output_dir()?;
+            tiles
+                .iter()
+                .map(|tile| {
+                    let output_path = self.tile_path(path, tile.index, extension)?;
+                    fs::write(&output_path, &tile.bytes).with_context(|| {
+                        format!("Failed to save cropped image {}", output_path.display())
+                    })?;
+                    debug!(
+                        "Saved grid segment {} to {}",
+                        tile.index + 1,
+                        output_path.display()
+                    );
+                    Ok(output_path)
+                })
+                .collect()
+        }
+
+        /// Builds the lookup for a kept source palette
+        ///
+        /// When the tiles have transparent pixels, the source's transparent
+        /// index is reused if every frame agrees on it. Otherwise a slot is
+        /// appended, or the images are quantized when the palette is full.
+        fn source_lookup(&self, palette: SourcePalette, images: &[&RgbaImage]) -> PaletteLookup {
+            let SourcePalette {
+                mut colors,
+                transparent,
+            } = palette;
+            if !quantize::has_transparency(images) {
+                return PaletteLookup::new(&colors, None);
+            }
+
+            let shared = transparent.filter(|&index| (index as usize) < colors.len() / 3);
+            if let Some(index) = shared {
+                return PaletteLookup::new(&colors, Some(index));
+            }
+            if colors.len() / 3 < 256 {
+                let index = quantize::push_transparent_slot(&mut colors);
+                return PaletteLookup::new(&colors, Some(index));
             }
 
-            rgba
+            debug!("Source palette is full, quantizing to make room for transparency");
+            PaletteLookup::quantized(images, 256)
+        }
+
+        /// Converts RGBA pixels to indexed colors using the palette
+        fn convert_to_indexed_optimized(&self, rgba: &[u8], lookup: &PaletteLookup) -> Vec<u8> {
+            // Chunks are collected in order, so the output matches the pixel layout
+            let chunk_size = 1024;
+            rgba.par_chunks(4 * chunk_size)
+                .flat_map_iter(|chunk| {
+                    chunk.chunks(4 * 8).flat_map(|pixels| {
+                        let opaque = simd::opaque_mask(pixels);
+                        pixels.chunks_exact(4).enumerate().map(move |(i, pixel)| {
+                            match lookup.transparent() {
+                                Some(transparent) if opaque & (1 << i) == 0 => transparent,
+                                _ => lookup.nearest([pixel[0], pixel[1], pixel[2]]),
+                            }
+                        })
+                    })
+                })
+                .collect()
+        }
+    }
+
+    /// Detects the format of encoded image data from its magic bytes
+    ///
+    /// Fails with [`GridistError::UnsupportedFormat`] when the data is not
+    /// recognised or gridist cannot decode it.
+    pub fn detect_format(bytes: &[u8]) -> GridistResult<ImageFormat> {
+        let format = image::guess_format(bytes).map_err(|_| {
+            GridistError::UnsupportedFormat("file contents are not a known image type".to_string())
+        })?;
+        if !format.reading_enabled() {
+            return Err(GridistError::UnsupportedFormat(format!(
+                "{:?} images cannot be decoded",
+                format
+            )));
+        }
+        Ok(format)
+    }
+
+    /// Returns the extension for tiles of a `format` image read from `path`
+    ///
+    /// The file's own extension is kept when it names the detected format,
+    /// so `photo.JPG` gives `JPG` tiles and an extensionless PNG gives `png`.
+    fn tile_extension(path: &Path, format: ImageFormat) -> String {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|&ext| ImageFormat::from_extension(ext) == Some(format))
+            .unwrap_or(format.extensions_str()[0])
+            .to_string()
+    }
+
+    /// Returns the bounding rectangle `(left, top, width, height)` of the
+    /// pixels that differ between two indexed frames of the given width
+    ///
+    /// The frames must differ in at least one pixel.
+    fn changed_rect(previous: &[u8], current: &[u8], width: usize) -> (usize, usize, usize, usize) {
+        let (mut left, mut top, mut right, mut bottom) = (width, usize::MAX, 0, 0);
+        for (y, (old, new)) in previous
+            .chunks_exact(width)
+            .zip(current.chunks_exact(width))
+            .enumerate()
+        {
+            let Some(first) = old.iter().zip(new).position(|(a, b)| a != b) else {
+                continue;
+            };
+            let last = old
+                .iter()
+                .zip(new)
+                .rposition(|(a, b)| a != b)
+                .unwrap_or(first);
+            left = left.min(first);
+            right = right.max(last + 1);
+            top = top.min(y);
+            bottom = y + 1;
+        }
+        (left, top, right - left, bottom - top)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use proptest::collection::{hash_set, vec};
+        use proptest::prelude::*;
+
+        /// A palette of distinct colours and pixels drawn from it, longer
+        /// than one conversion chunk and not a multiple of the SIMD width
+        fn palette_exact_image() -> impl Strategy<Value = (Vec<[u8; 3]>, Vec<(usize, u8)>)> {
+            hash_set(any::<[u8; 3]>(), 1..=255)
+                .prop_map(|colors| colors.into_iter().collect::<Vec<_>>())
+                .prop_flat_map(|colors| {
+                    let count = colors.len();
+                    let pixels = vec((0..count, any::<u8>()), 1025..5000);
+                    (Just(colors), pixels)
+                })
+        }
+
+        /// A 2x2 grid of 16x8 cuts whose 40x26 canvas needs no resizing
+        fn small_cropper() -> ImageCropper {
+            let config = config::ImageConfig {
+                container_width: 40,
+                cut_width: 16,
+                cut_height: 8,
+                card_padding_top: 2,
+                card_padding_horizontal: 2,
+                card_padding_bottom: 2,
+                card_margin_bottom: 2,
+                rows: 2,
+                cols: 2,
+                filter: config::ResizeFilter::Nearest,
+                ..config::ImageConfig::default()
+            };
+            ImageCropper::new(config).unwrap()
+        }
+
+        /// Encodes a 40x26 GIF with red, green, blue and white at indices 0-3
+        ///
+        /// The first frame covers the canvas; its top six rows use index 3.
+        /// The second frame covers rows 6-19 with index 2.
+        fn two_frame_gif(first_transparent: Option<u8>, second_transparent: Option<u8>) -> Vec<u8> {
+            let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+            let mut bytes = Vec::new();
+            {
+                let mut encoder = Encoder::new(&mut bytes, 40, 26, &palette).unwrap();
+                let first: Vec<u8> = (0..26 * 40)
+                    .map(|i| match (i % 40, i / 40) {
+                        (_, 0..=5) => 3,
+                        (0..=19, _) => 0,
+                        _ => 1,
+                    })
+                    .collect();
+                let mut frame = Frame::from_indexed_pixels(40, 26, first, first_transparent);
+                frame.delay = 10;
+                encoder.write_frame(&frame).unwrap();
+
+                let mut frame =
+                    Frame::from_indexed_pixels(40, 14, vec![2; 40 * 14], second_transparent);
+                frame.top = 6;
+                frame.delay = 10;
+                encoder.write_frame(&frame).unwrap();
+            }
+            bytes
+        }
+
+        /// Returns the palette size and the transparent index of every frame of every tile
+        fn tile_transparency(tiles: &[Tile]) -> Vec<(usize, Vec<Option<u8>>)> {
+            tiles
+                .iter()
+                .map(|tile| {
+                    let mut decoder = gif::DecodeOptions::new()
+                        .read_info(Cursor::new(&tile.bytes))
+                        .unwrap();
+                    let colors = decoder.global_palette().map_or(0, |p| p.len() / 3);
+                    let mut transparent = Vec::new();
+                    while let Some(frame) = decoder.read_next_frame().unwrap() {
+                        transparent.push(frame.transparent);
+                    }
+                    (colors, transparent)
+                })
+                .collect()
+        }
+
+        #[test]
+        fn gif_tiles_reuse_the_source_transparent_index() {
+            let bytes = two_frame_gif(Some(3), Some(3));
+            let tiles = small_cropper().crop_gif_bytes(&bytes).unwrap();
+            for (colors, transparent) in tile_transparency(&tiles) {
+                assert_eq!(colors, 4);
+                assert!(transparent.iter().all(|&index| index == Some(3)));
+            }
+        }
+
+        #[test]
+        fn gif_tiles_share_an_appended_slot_when_frames_disagree() {
+            let bytes = two_frame_gif(Some(3), Some(1));
+            let tiles = small_cropper().crop_gif_bytes(&bytes).unwrap();
+            // The slot is appended after the 4 colours; the encoder pads to 8
+            for (colors, transparent) in tile_transparency(&tiles) {
+                assert_eq!(colors, 8);
+                assert!(transparent.iter().all(|&index| index == Some(4)));
+            }
+        }
+
+        #[test]
+        fn gif_tiles_without_transparent_pixels_have_no_slot() {
+            // The first frame declares index 2 transparent but never uses it
+            let bytes = two_frame_gif(Some(2), None);
+            let mut tiles = small_cropper().crop_gif_bytes(&bytes).unwrap();
+            let opaque = two_frame_gif(None, None);
+            tiles.extend(small_cropper().crop_gif_bytes(&opaque).unwrap());
+            for (colors, transparent) in tile_transparency(&tiles) {
+                assert_eq!(colors, 4);
+                assert!(transparent.iter().all(Option::is_none));
+            }
+        }
+
+        /// Red, green, blue and white, as a flat palette
+        const RGBW: [u8; 12] = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+        /// Returns the position, size, delay and disposal of every frame of a GIF
+        fn frame_rects(bytes: &[u8]) -> Vec<(u16, u16, u16, u16, u16, DisposalMethod)> {
+            let mut decoder = gif::DecodeOptions::new()
+                .read_info(Cursor::new(bytes))
+                .unwrap();
+            let mut rects = Vec::new();
+            while let Some(frame) = decoder.read_next_frame().unwrap() {
+                rects.push((
+                    frame.left,
+                    frame.top,
+                    frame.width,
+                    frame.height,
+                    frame.delay,
+                    frame.dispose,
+                ));
+            }
+            rects
+        }
+
+        #[test]
+        fn changed_rect_bounds_the_differing_pixels() {
+            let previous = vec![0u8; 6 * 4];
+            let mut current = previous.clone();
+            current[6 + 1] = 1;
+            current[2 * 6 + 4] = 2;
+            assert_eq!(changed_rect(&previous, &current, 6), (1, 1, 4, 2));
+
+            let mut corner = previous.clone();
+            corner[3 * 6 + 5] = 1;
+            assert_eq!(changed_rect(&previous, &corner, 6), (5, 3, 1, 1));
+        }
+
+        #[test]
+        fn gif_tiles_store_changed_rects_and_merge_repeats() {
+            let red = image::Rgba([255, 0, 0, 255]);
+            let first = RgbaImage::from_pixel(16, 8, red);
+            let mut second = first.clone();
+            for (x, y) in (5..7).flat_map(|x| (2..5).map(move |y| (x, y))) {
+                second.put_pixel(x, y, image::Rgba([0, 0, 255, 255]));
+            }
+            let mut fourth = second.clone();
+            fourth.put_pixel(10, 6, image::Rgba([0, 255, 0, 255]));
+
+            let frames = [&first, &second, &second, &fourth];
+            let lookup = PaletteLookup::new(&RGBW, None);
+            let bytes = small_cropper()
+                .encode_gif_tile(&[10, 20, 30, 40], &frames, &lookup, (0, 0))
+                .unwrap();
+
+            assert_eq!(
+                frame_rects(&bytes),
+                [
+                    (0, 0, 16, 8, 10, DisposalMethod::Keep),
+                    (5, 2, 2, 3, 50, DisposalMethod::Keep),
+                    (10, 6, 1, 1, 40, DisposalMethod::Keep),
+                ]
+            );
+            // Composited, the kept frames rebuild every distinct source frame
+            let mut source = animation::open(&bytes, ImageFormat::Gif).unwrap().unwrap();
+            for expected in [&first, &second, &fourth] {
+                let frame = source.next_frame().unwrap().unwrap();
+                assert_eq!(&frame.image, expected);
+            }
+        }
+
+        #[test]
+        fn gif_tiles_without_changes_become_one_frame() {
+            let still = RgbaImage::from_pixel(16, 8, image::Rgba([255, 255, 255, 255]));
+            let lookup = PaletteLookup::new(&RGBW, None);
+            let bytes = small_cropper()
+                .encode_gif_tile(&[10, 20, 30], &[&still, &still, &still], &lookup, (0, 0))
+                .unwrap();
+            assert_eq!(
+                frame_rects(&bytes),
+                [(0, 0, 16, 8, 60, DisposalMethod::Keep)]
+            );
+        }
+
+        #[test]
+        fn gif_tiles_with_transparency_use_full_frames() {
+            let first = RgbaImage::from_pixel(16, 8, image::Rgba([255, 0, 0, 255]));
+            let mut second = first.clone();
+            second.put_pixel(3, 3, image::Rgba([0, 0, 0, 0]));
+
+            let mut palette = RGBW.to_vec();
+            let slot = quantize::push_transparent_slot(&mut palette);
+            let lookup = PaletteLookup::new(&palette, Some(slot));
+            let bytes = small_cropper()
+                .encode_gif_tile(&[10, 10], &[&first, &second], &lookup, (0, 0))
+                .unwrap();
+            assert_eq!(
+                frame_rects(&bytes),
+                [
+                    (0, 0, 16, 8, 10, DisposalMethod::Background),
+                    (0, 0, 16, 8, 10, DisposalMethod::Background),
+                ]
+            );
+            let mut decoder = gif::DecodeOptions::new()
+                .read_info(Cursor::new(&bytes))
+                .unwrap();
+            while let Some(frame) = decoder.read_next_frame().unwrap() {
+                assert_eq!(frame.transparent, Some(slot));
+            }
+        }
+
+        /// An opaque image of xorshift noise, which compresses poorly
+        fn noise(width: u32, height: u32, seed: u32) -> RgbaImage {
+            let mut state = seed | 1;
+            RgbaImage::from_fn(width, height, |_, _| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let [r, g, b, _] = state.to_le_bytes();
+                image::Rgba([r, g, b, 255])
+            })
+        }
+
+        #[test]
+        fn reduced_gif_tiles_fit_and_report_their_changes() {
+            let frames: Vec<RgbaImage> = (1..=6).map(|seed| noise(16, 8, seed)).collect();
+            let images: Vec<&RgbaImage> = frames.iter().collect();
+            let delays = [10; 6];
+            let lookup = PaletteLookup::quantized(&images, 256);
+            let cropper = small_cropper();
+            let bytes = cropper
+                .encode_gif_tile(&delays, &images, &lookup, (0, 0))
+                .unwrap();
+            let budget = ByteSize(bytes.len() as u64 / 3);
+
+            let (bytes, changes) = cropper
+                .reduce_gif_tile(bytes, budget, &delays, &images, &lookup, (0, 0))
+                .unwrap();
+            assert!(bytes.len() as u64 <= budget.0, "{} bytes", bytes.len());
+
+            let mut decoder = gif::DecodeOptions::new()
+                .read_info(Cursor::new(&bytes))
+                .unwrap();
+            let palette = decoder.global_palette().unwrap().len() / 3;
+            let mut frames = 0;
+            while decoder.read_next_frame().unwrap().is_some() {
+                frames += 1;
+            }
+            let [Reduction::Colors(colors), Reduction::Frames { kept, total: 6 }] = changes[..]
+            else {
+                panic!("unexpected changes {:?}", changes);
+            };
+            assert!(palette <= colors, "{} colours", palette);
+            assert_eq!(frames, kept);
+        }
+
+        #[test]
+        fn reduced_png_tiles_are_indexed_and_fit() {
+            let tile =
+                DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(noise(64, 64, 7)).to_rgb8());
+            let cropper = small_cropper();
+            let bytes =
+                encode::encode_image(&tile, ImageFormat::Png, &Encoding::default()).unwrap();
+            let budget = ByteSize(bytes.len() as u64 / 4);
+
+            let (bytes, change) = cropper
+                .reduce_static_tile(bytes, budget, &tile, ImageFormat::Png)
+                .unwrap();
+            assert!(bytes.len() as u64 <= budget.0, "{} bytes", bytes.len());
+            let Some(Reduction::Colors(colors)) = change else {
+                panic!("unexpected change {:?}", change);
+            };
+
+            let decoder = png::Decoder::new(Cursor::new(&bytes));
+            let reader = decoder.read_info().unwrap();
+            let info = reader.info();
+            assert_eq!(info.color_type, png::ColorType::Indexed);
+            assert!(info.palette.as_ref().unwrap().len() / 3 <= colors);
+        }
+
+        #[test]
+        fn reduced_jpeg_tiles_report_the_quality_used() {
+            let tile = DynamicImage::ImageRgba8(noise(64, 64, 11));
+            let cropper = small_cropper();
+            let bytes =
+                encode::encode_image(&tile, ImageFormat::Jpeg, &Encoding::default()).unwrap();
+            let budget = ByteSize(bytes.len() as u64 * 2 / 3);
+
+            let (bytes, change) = cropper
+                .reduce_static_tile(bytes, budget, &tile, ImageFormat::Jpeg)
+                .unwrap();
+            assert!(bytes.len() as u64 <= budget.0, "{} bytes", bytes.len());
+            let Some(Reduction::Quality(quality)) = change else {
+                panic!("unexpected change {:?}", change);
+            };
+            let encoding = Encoding {
+                jpeg_quality: quality,
+                ..Encoding::default()
+            };
+            assert_eq!(
+                bytes,
+                encode::encode_image(&tile, ImageFormat::Jpeg, &encoding).unwrap()
+            );
+        }
+
+        #[test]
+        fn image_bytes_are_cut_into_tiles_in_memory() {
+            let source = noise(40, 26, 3);
+            let mut bytes = Vec::new();
+            source
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+                .unwrap();
+            let cropper = small_cropper();
+            let tiles = cropper.crop_image_bytes(&bytes).unwrap();
+
+            let positions: Vec<_> = tiles.iter().map(|t| (t.index, t.row, t.col)).collect();
+            assert_eq!(positions, [(0, 0, 0), (1, 0, 1), (2, 1, 0), (3, 1, 1)]);
+            for tile in &tiles {
+                assert_eq!(tile.mime, "image/png");
+                let decoded = image::load_from_memory(&tile.bytes).unwrap().to_rgba8();
+                // The canvas is already grid-sized, so tiles are exact cuts
+                let (x, y) = cropper.get_xy(tile.index);
+                let expected = image::imageops::crop_imm(&source, x, y, 16, 8).to_image();
+                assert_eq!(decoded, expected);
+            }
+        }
+
+        #[test]
+        fn dynamic_images_are_cut_into_tiles_of_the_output_format() {
+            let image = DynamicImage::ImageRgba8(noise(40, 26, 5));
+            let mut cropper = small_cropper();
+            let tiles = cropper
+                .crop_dynamic_image(&image, ImageFormat::Jpeg)
+                .unwrap();
+            assert_eq!(tiles.len(), 4);
+            for (i, tile) in tiles.iter().enumerate() {
+                assert_eq!(
+                    (tile.index, tile.row, tile.col),
+                    (i as u32, i as u32 / 2, i as u32 % 2)
+                );
+                assert_eq!(tile.mime, "image/jpeg");
+                let decoded = image::load_from_memory_with_format(&tile.bytes, ImageFormat::Jpeg);
+                assert_eq!(decoded.unwrap().dimensions(), (16, 8));
+            }
+
+            cropper
+                .set_encoding(Encoding {
+                    format: Some(encode::OutputFormat::Gif),
+                    ..Encoding::default()
+                })
+                .unwrap();
+            let tiles = cropper
+                .crop_dynamic_image(&image, ImageFormat::Jpeg)
+                .unwrap();
+            for tile in &tiles {
+                assert_eq!(tile.mime, "image/gif");
+                let decoded = image::load_from_memory_with_format(&tile.bytes, ImageFormat::Gif);
+                assert_eq!(decoded.unwrap().dimensions(), (16, 8));
+            }
+        }
+
+        #[test]
+        fn detect_format_ignores_names_and_extensions() {
+            let image = DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(noise(4, 4, 9)).to_rgb8());
+            let dir = tempfile::TempDir::new().unwrap();
+            for (format, name) in [
+                (ImageFormat::Gif, "animation"),
+                (ImageFormat::Gif, "ANIMATION.GIF"),
+                (ImageFormat::Png, "picture.jpg"),
+                (ImageFormat::Jpeg, "photo.png"),
+                (ImageFormat::WebP, "sticker.gif"),
+            ] {
+                let path = dir.path().join(name);
+                image.save_with_format(&path, format).unwrap();
+                let bytes = fs::read(&path).unwrap();
+                assert_eq!(detect_format(&bytes).unwrap(), format, "{}", name);
+            }
+        }
+
+        #[test]
+        fn tile_extensions_follow_the_detected_format() {
+            assert_eq!(tile_extension(Path::new("a.GIF"), ImageFormat::Gif), "GIF");
+            assert_eq!(
+                tile_extension(Path::new("a.jpeg"), ImageFormat::Jpeg),
+                "jpeg"
+            );
+            assert_eq!(tile_extension(Path::new("a"), ImageFormat::Png), "png");
+            assert_eq!(tile_extension(Path::new("a.jpg"), ImageFormat::Png), "png");
+        }
+
+        #[test]
+        fn detect_format_rejects_unknown_data() {
+            for bytes in [&b""[..], b"not an image", &[0u8; 64]] {
+                assert!(matches!(
+                    detect_format(bytes),
+                    Err(GridistError::UnsupportedFormat(_))
+                ));
+            }
+        }
+
+        /// The small cropper with another fit mode and background
+        fn fit_cropper(fit: Fit, background: Background) -> ImageCropper {
+            let mut config = small_cropper().config().clone();
+            config.fit = fit;
+            config.background = background;
+            ImageCropper::new(config).unwrap()
+        }
+
+        /// A red image whose right half is blue
+        fn red_blue(width: u32, height: u32) -> DynamicImage {
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+                if x < width / 2 {
+                    image::Rgba([255, 0, 0, 255])
+                } else {
+                    image::Rgba([0, 0, 255, 255])
+                }
+            }))
+        }
+
+        #[test]
+        fn every_fit_mode_covers_the_grid() {
+            let sizes = [(80, 20), (10, 30), (39, 25), (41, 27), (1000, 3), (3, 1000)];
+            for fit in [Fit::Cover, Fit::Contain, Fit::Pad, Fit::Stretch] {
+                let cropper = fit_cropper(fit, Background::default());
+                for (width, height) in sizes {
+                    let (resized, (x, y)) = cropper.resize_for_grid(&red_blue(width, height));
+                    let (resized_width, resized_height) = resized.dimensions();
+                    assert!(
+                        x + 40 <= resized_width && y + 26 <= resized_height,
+                        "{:?} {}x{} gave {}x{} at ({}, {})",
+                        fit,
+                        width,
+                        height,
+                        resized_width,
+                        resized_height,
+                        x,
+                        y
+                    );
+                    if fit != Fit::Cover {
+                        assert_eq!((resized_width, resized_height, x, y), (40, 26, 0, 0));
+                    }
+                    let tiles = cropper.crop_tiles(&red_blue(width, height));
+                    assert!(tiles.iter().all(|tile| tile.dimensions() == (16, 8)));
+                }
+            }
+        }
+
+        #[test]
+        fn fit_modes_keep_the_aspect_ratio_unless_stretching() {
+            let size = |fit, width, height| {
+                fit_cropper(fit, Background::default()).calculate_resize_dimensions(width, height)
+            };
+            assert_eq!(size(Fit::Cover, 80, 20), (104, 26));
+            assert_eq!(size(Fit::Cover, 10, 30), (40, 120));
+            assert_eq!(size(Fit::Contain, 80, 20), (40, 10));
+            assert_eq!(size(Fit::Contain, 10, 5), (40, 20));
+            // Pad never enlarges
+            assert_eq!(size(Fit::Pad, 80, 20), (40, 10));
+            assert_eq!(size(Fit::Pad, 10, 5), (10, 5));
+            assert_eq!(size(Fit::Stretch, 80, 20), (40, 26));
+        }
+
+        #[test]
+        fn contained_images_are_padded_with_the_background() {
+            let white = [255, 255, 255, 255];
+            for (fit, width, height, inside) in [
+                (Fit::Contain, 80, 20, (10, 13)),
+                (Fit::Pad, 10, 4, (15, 12)),
+            ] {
+                let cropper = fit_cropper(fit, Background::Color(white));
+                let (canvas, _) = cropper.resize_for_grid(&red_blue(width, height));
+                let canvas = canvas.to_rgba8();
+                assert_eq!(canvas.get_pixel(0, 0).0, white, "{:?}", fit);
+                assert_eq!(canvas.get_pixel(39, 25).0, white, "{:?}", fit);
+                assert_eq!(
+                    canvas.get_pixel(inside.0, inside.1).0,
+                    [255, 0, 0, 255],
+                    "{:?}",
+                    fit
+                );
+            }
+        }
+
+        #[test]
+        fn blurred_backgrounds_follow_the_image() {
+            let cropper = fit_cropper(Fit::Contain, Background::Blur);
+            let (canvas, _) = cropper.resize_for_grid(&red_blue(80, 20));
+            let canvas = canvas.to_rgba8();
+            // The image covers rows 8 to 17; the rows above and below are blurred
+            let [r, _, b, a] = canvas.get_pixel(2, 0).0;
+            assert!(a == 255 && r > b, "left edge is {:?}", [r, b, a]);
+            let [r, _, b, a] = canvas.get_pixel(37, 25).0;
+            assert!(a == 255 && b > r, "right edge is {:?}", [r, b, a]);
+        }
+
+        /// The small cropper framed with `framing`
+        fn framed_cropper(framing: Framing) -> ImageCropper {
+            let mut cropper = small_cropper();
+            cropper.set_framing(framing).unwrap();
+            cropper
+        }
+
+        #[test]
+        fn focus_offsets_stay_inside_the_image() {
+            let offsets = |x, y, width, height| {
+                framed_cropper(Framing::Focus(FocusPoint { x, y })).crop_offsets(width, height)
+            };
+            // A 100x60 image leaves 60x34 pixels around the 40x26 grid
+            assert_eq!(offsets(0.0, 0.0, 100, 60), (0, 0));
+            assert_eq!(offsets(1.0, 1.0, 100, 60), (60, 34));
+            assert_eq!(offsets(0.5, 0.5, 100, 60), (30, 17));
+            assert_eq!(offsets(0.9, 0.1, 100, 60), (60, 0));
+            assert_eq!(offsets(1.0, 0.0, 40, 26), (0, 0));
+        }
+
+        #[test]
+        fn crop_regions_are_clamped_to_the_source() {
+            let rect = |x, y, width, height| CropRect {
+                x,
+                y,
+                width,
+                height,
+            };
+            assert_eq!(rect(30, 10, 50, 50).clamped(40, 26), rect(30, 10, 10, 16));
+            assert_eq!(rect(100, 100, 5, 5).clamped(40, 26), rect(39, 25, 1, 1));
+            assert_eq!(rect(0, 0, 40, 26).clamped(40, 26), rect(0, 0, 40, 26));
+
+            // Only the blue right half lies inside a region hanging off the right edge
+            for fit in [Fit::Cover, Fit::Contain, Fit::Stretch] {
+                let mut config = small_cropper().config().clone();
+                config.fit = fit;
+                let mut cropper = ImageCropper::new(config).unwrap();
+                cropper
+                    .set_framing(Framing::Region(rect(20, 0, 100, 100)))
+                    .unwrap();
+                for tile in cropper.crop_tiles(&red_blue(40, 26)) {
+                    assert!(
+                        tile.pixels().all(|p| p[3] == 0 || p.0 == [0, 0, 255, 255]),
+                        "{:?}",
+                        fit
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn smart_crop_moves_towards_detail() {
+            // A flat strip with a checkerboard far right of the centre
+            let image = RgbaImage::from_fn(120, 26, |x, y| {
+                let value = if (90..110).contains(&x) && (x / 2 + y / 2) % 2 == 0 {
+                    255
+                } else {
+                    96
+                };
+                image::Rgba([value, value, value, 255])
+            });
+            let cropper = framed_cropper(Framing::Smart);
+            let smart = cropper.smart_offsets(&[&image]);
+            let centre = (40, 0);
+            assert!(smart.0 > centre.0, "smart crop stayed at {:?}", smart);
+
+            let visible = |offsets| cropper.gutter_report(&[&image], offsets).unwrap().visible;
+            assert!(visible(smart) > 2.0 * visible(centre));
+            let (resized, offsets) = cropper.resize_for_grid(&DynamicImage::ImageRgba8(image));
+            assert_eq!((resized.dimensions(), offsets), ((120, 26), smart));
+        }
+
+        #[test]
+        fn gutter_shift_uncovers_an_edge_hidden_in_a_gutter() {
+            // Centred, the grid starts at x=10 and the columns' gutter covers
+            // x=28..32, hiding the edge at x=30
+            let image = RgbaImage::from_fn(60, 26, |x, _| {
+                let value = if x < 30 { 0 } else { 255 };
+                image::Rgba([value, value, value, 255])
+            });
+            let mut cropper = small_cropper();
+            cropper.set_gutters(GutterOptions {
+                report: false,
+                max_shift: 6,
+            });
+            let (_, shifted) = cropper.resize_for_grid(&DynamicImage::ImageRgba8(image.clone()));
+            let centre = (10, 0);
+            assert_ne!(shifted, centre);
+
+            let report = |offsets| cropper.gutter_report(&[&image], offsets).unwrap();
+            assert!(report(centre).hidden_share() > 0.9);
+            assert!(report(shifted).hidden() < report(centre).hidden());
+        }
+
+        #[test]
+        fn source_lookup_reserves_a_slot_only_below_half_alpha() {
+            let palette = SourcePalette {
+                colors: vec![255, 0, 0, 0, 255, 0],
+                transparent: None,
+            };
+            let cropper = ImageCropper::default();
+            let translucent = RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 128]));
+            let lookup = cropper.source_lookup(palette.clone(), &[&translucent]);
+            assert_eq!(lookup.transparent(), None);
+
+            let mut transparent = translucent.clone();
+            transparent.put_pixel(3, 3, image::Rgba([0, 0, 0, 127]));
+            let lookup = cropper.source_lookup(palette, &[&transparent]);
+            assert_eq!(lookup.transparent(), Some(2));
+            assert_eq!(lookup.palette().len(), 9);
+        }
+
+        #[test]
+        fn source_lookup_quantizes_a_full_palette_to_make_room() {
+            let colors: Vec<u8> = (0..=255u8).flat_map(|i| [i, 255 - i, i / 2]).collect();
+            let palette = SourcePalette {
+                colors: colors.clone(),
+                transparent: None,
+            };
+            let mut image = RgbaImage::from_fn(16, 16, |x, y| {
+                let i = (y * 16 + x) as u8;
+                image::Rgba([i, 255 - i, i / 2, 255])
+            });
+            image.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+
+            let lookup = ImageCropper::default().source_lookup(palette, &[&image]);
+            let slot = lookup.transparent().expect("a transparent slot");
+            assert!(lookup.palette().len() <= 256 * 3);
+            assert_ne!(lookup.palette(), &colors[..]);
+            // Opaque pixels never map to the slot
+            for pixel in image.pixels().skip(1) {
+                assert_ne!(lookup.index(pixel.0), slot);
+            }
+            assert_eq!(lookup.index([0, 0, 0, 0]), slot);
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(16))]
+
+            #[test]
+            fn indexed_round_trip_keeps_palette_exact_pixels(
+                (colors, pixels) in palette_exact_image(),
+                with_transparency in any::<bool>(),
+            ) {
+                let mut palette: Vec<u8> = colors.iter().flatten().copied().collect();
+                let transparent = with_transparency
+                    .then(|| quantize::push_transparent_slot(&mut palette));
+                let lookup = PaletteLookup::new(&palette, transparent);
+
+                // Alpha below 128 is transparent when the palette has a slot for it
+                let rgba: Vec<u8> = pixels
+                    .iter()
+                    .flat_map(|&(index, alpha)| {
+                        let alpha = if with_transparency { alpha } else { alpha | 0x80 };
+                        let [r, g, b] = colors[index];
+                        [r, g, b, alpha]
+                    })
+                    .collect();
+                let indexed = ImageCropper::default().convert_to_indexed_optimized(&rgba, &lookup);
+                prop_assert_eq!(indexed.len(), pixels.len());
+
+                for (position, (&index, original)) in
+                    indexed.iter().zip(rgba.chunks_exact(4)).enumerate()
+                {
+                    if original[3] < 128 {
+                        prop_assert_eq!(Some(index), transparent, "pixel {}", position);
+                        continue;
+                    }
+                    let color = &palette[index as usize * 3..index as usize * 3 + 3];
+                    prop_assert_eq!(color, &original[..3], "pixel {}", position);
+                }
+            }
+        }
+    }
+}
+
+/// Animated image decoding and frame compositing
+pub mod animation {
+    use super::*;
+    use gif::DisposalMethod;
+    use image::codecs::png::PngDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use image::{AnimationDecoder, Frames, ImageDecoder};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    /// A frame of an animation, composited onto the full canvas
+    pub struct AnimationFrame {
+        pub image: RgbaImage,
+        /// Delay before the next frame, in hundredths of a second
+        pub delay: u16,
+    }
+
+    /// The palette of an indexed animation whose frames all share it
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SourcePalette {
+        pub colors: Vec<u8>,
+        /// Transparent index, when every frame uses the same one
+        pub transparent: Option<u8>,
+    }
+
+    /// A source of composited animation frames
+    pub trait AnimationSource {
+        /// Returns the canvas size of the animation
+        fn dimensions(&self) -> (u32, u32);
+
+        /// Decodes the next frame, or returns `None` after the last one
+        fn next_frame(&mut self) -> GridistResult<Option<AnimationFrame>>;
+
+        /// Returns the palette shared by every frame read so far
+        ///
+        /// Only indexed formats have one; call it after reading all frames.
+        fn shared_palette(&self) -> Option<SourcePalette> {
+            None
+        }
+    }
+
+    /// Opens `bytes` as an animation when `format` supports one
+    ///
+    /// GIFs are always animations, even with a single frame. Still PNG and
+    /// WebP images, and every other format, give `None`.
+    pub fn open<'a>(
+        bytes: &'a [u8],
+        format: ImageFormat,
+    ) -> GridistResult<Option<Box<dyn AnimationSource + 'a>>> {
+        match format {
+            ImageFormat::Gif => Ok(Some(Box::new(GifSource::new(bytes)?))),
+            ImageFormat::Png => {
+                let decoder = PngDecoder::new(Cursor::new(bytes))?;
+                if !decoder.is_apng()? {
+                    return Ok(None);
+                }
+                let dimensions = decoder.dimensions();
+                let frames = decoder.apng()?.into_frames();
+                Ok(Some(Box::new(DecodedSource { frames, dimensions })))
+            }
+            ImageFormat::WebP => {
+                let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+                if !decoder.has_animation() {
+                    return Ok(None);
+                }
+                let dimensions = decoder.dimensions();
+                let frames = decoder.into_frames();
+                Ok(Some(Box::new(DecodedSource { frames, dimensions })))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns whether `bytes` hold an animation that [`open`] accepts
+    ///
+    /// Only the headers are checked: an `acTL` chunk before the image data
+    /// of a PNG, or the animation flag of a WebP's `VP8X` chunk.
+    pub fn is_animated(bytes: &[u8], format: ImageFormat) -> bool {
+        match format {
+            ImageFormat::Gif => true,
+            ImageFormat::Png => has_actl_chunk(bytes),
+            ImageFormat::WebP => {
+                bytes.len() > 20 && &bytes[12..16] == b"VP8X" && bytes[20] & 0x02 != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Walks the PNG chunks before `IDAT` looking for an `acTL` chunk
+    fn has_actl_chunk(bytes: &[u8]) -> bool {
+        // The 8-byte signature is followed by length, type, data and CRC
+        let mut offset = 8;
+        while let Some(header) = bytes.get(offset..offset + 8) {
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+            match &header[4..8] {
+                b"acTL" => return true,
+                b"IDAT" | b"IEND" => return false,
+                _ => offset += 12 + length as usize,
+            }
+        }
+        false
+    }
+
+    /// GIF frames decoded with their own palettes and composited in order
+    pub struct GifSource<'a> {
+        decoder: Decoder<&'a [u8]>,
+        compositor: GifCompositor,
+        global_palette: Option<Vec<u8>>,
+        default_palette: Vec<u8>,
+        /// Palette and transparent index shared so far, `None` once frames differ
+        shared: Option<SourcePalette>,
+        frames_read: usize,
+    }
+
+    impl<'a> GifSource<'a> {
+        /// Reads the GIF header from `bytes`
+        pub fn new(bytes: &'a [u8]) -> GridistResult<Self> {
+            let decoder = Decoder::new(bytes).with_context(|| "Failed to create GIF decoder")?;
+            let compositor = GifCompositor::new(decoder.width(), decoder.height());
+            let global_palette = decoder.global_palette().map(|p| p.to_vec());
+            Ok(Self {
+                decoder,
+                compositor,
+                global_palette,
+                default_palette: create_default_palette(),
+                shared: None,
+                frames_read: 0,
+            })
+        }
+    }
+
+    impl AnimationSource for GifSource<'_> {
+        fn dimensions(&self) -> (u32, u32) {
+            (self.decoder.width() as u32, self.decoder.height() as u32)
+        }
+
+        fn next_frame(&mut self) -> GridistResult<Option<AnimationFrame>> {
+            let Some(frame) = self
+                .decoder
+                .read_next_frame()
+                .with_context(|| "Failed to read GIF frame")?
+            else {
+                return Ok(None);
+            };
+
+            // Frames are decoded with their local palette when they carry one
+            let palette = frame.palette.as_deref().or(self.global_palette.as_deref());
+            let current = palette.map(|colors| SourcePalette {
+                colors: colors.to_vec(),
+                transparent: frame.transparent,
+            });
+            let was_shared = self.shared.is_some();
+            self.shared = match (self.frames_read, self.shared.take(), current) {
+                (0, _, current) => current,
+                (_, Some(shared), Some(current)) if shared.colors == current.colors => {
+                    Some(SourcePalette {
+                        transparent: shared
+                            .transparent
+                            .filter(|&index| current.transparent == Some(index)),
+                        ..shared
+                    })
+                }
+                _ => None,
+            };
+            if was_shared && self.shared.is_none() {
+                debug!("Frames use several palettes, quantizing instead");
+            }
+            self.frames_read += 1;
+
+            let rgba = convert_to_rgba_optimized(frame, palette.unwrap_or(&self.default_palette));
+            let image = self.compositor.composite(frame, &rgba)?;
+            Ok(Some(AnimationFrame {
+                image,
+                delay: frame.delay,
+            }))
+        }
+
+        fn shared_palette(&self) -> Option<SourcePalette> {
+            self.shared.clone()
+        }
+    }
+
+    /// Frames of an animation decoded by the image crate, which composites them
+    struct DecodedSource<'a> {
+        frames: Frames<'a>,
+        dimensions: (u32, u32),
+    }
+
+    impl AnimationSource for DecodedSource<'_> {
+        fn dimensions(&self) -> (u32, u32) {
+            self.dimensions
+        }
+
+        fn next_frame(&mut self) -> GridistResult<Option<AnimationFrame>> {
+            let Some(frame) = self.frames.next().transpose()? else {
+                return Ok(None);
+            };
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay = numerator as f64 / denominator.max(1) as f64 / 10.0;
+            Ok(Some(AnimationFrame {
+                delay: delay.round().min(u16::MAX as f64) as u16,
+                image: frame.into_buffer(),
+            }))
+        }
+    }
+
+    /// Creates a default color palette for decoding GIFs that lack one
+    fn create_default_palette() -> Vec<u8> {
+        let mut palette = Vec::with_capacity(768);
+
+        let base_colors = [
+            (255, 0, 0),     // Red
+            (0, 255, 0),     // Green
+            (0, 0, 255),     // Blue
+            (255, 255, 0),   // Yellow
+            (255, 0, 255),   // Magenta
+            (0, 255, 255),   // Cyan
+            (255, 255, 255), // White
+            (0, 0, 0),       // Black
+        ];
+
+        for &(r, g, b) in &base_colors {
+            palette.push(r);
+            palette.push(g);
+            palette.push(b);
+        }
+
+        for i in 0..31 {
+            palette.push((i * 8) as u8);
+            palette.push((i * 8) as u8);
+            palette.push((i * 8) as u8);
+        }
+
+        while palette.len() < 768 {
+            palette.push(0);
+            palette.push(0);
+            palette.push(0);
+        }
+
+        palette
+    }
+
+    /// Converts indexed colors to RGBA using the palette
+    fn convert_to_rgba_optimized(frame: &Frame, palette: &[u8]) -> Vec<u8> {
+        // Expand through a table of all 256 indices so the per-pixel work
+        // is a single 4-byte copy; indices past the palette stay black
+        let mut table = [[0u8, 0, 0, 255]; 256];
+        for (entry, color) in table.iter_mut().zip(palette.chunks_exact(3)) {
+            entry[..3].copy_from_slice(color);
+        }
+        if let Some(transparent) = frame.transparent {
+            table[transparent as usize][3] = 0;
+        }
+
+        let mut rgba = vec![0u8; frame.buffer.len() * 4];
+        for (pixel, &index) in rgba.chunks_exact_mut(4).zip(frame.buffer.iter()) {
+            pixel.copy_from_slice(&table[index as usize]);
+        }
+        rgba
+    }
+
+    /// Composites GIF frames onto a canvas of the logical screen size
+    ///
+    /// GIF frames only store the sub-rectangle that changed, placed at
+    /// `left`/`top`. The disposal method of each frame decides what the canvas
+    /// looks like before the next frame is drawn on top of it.
+    pub struct GifCompositor {
+        canvas: RgbaImage,
+        /// Canvas saved before drawing a frame with `DisposalMethod::Previous`
+        saved: Option<RgbaImage>,
+        /// Disposal of the last drawn frame, applied before the next one
+        pending: Option<(DisposalMethod, u32, u32, u32, u32)>,
+    }
+
+    impl GifCompositor {
+        /// Creates a compositor with a transparent canvas of the given size
+        pub fn new(width: u16, height: u16) -> Self {
+            Self {
+                canvas: RgbaImage::new(width as u32, height as u32),
+                saved: None,
+                pending: None,
+            }
+        }
+
+        /// Draws a frame onto the canvas and returns the composited result
+        ///
+        /// `rgba` holds the frame's own pixels, `frame.width * frame.height * 4`
+        /// bytes, with fully transparent pixels left untouched on the canvas.
+        pub fn composite(&mut self, frame: &Frame, rgba: &[u8]) -> GridistResult<RgbaImage> {
+            let (width, height) = (frame.width as u32, frame.height as u32);
+            if rgba.len() != (width * height * 4) as usize {
+                return Err(GridistError::ImageProcessingError(
+                    image::ImageError::Limits(image::error::LimitError::from_kind(
+                        image::error::LimitErrorKind::DimensionError,
+                    )),
+                ));
+            }
+
+            self.dispose_previous();
+
+            if frame.dispose == DisposalMethod::Previous {
+                self.saved = Some(self.canvas.clone());
+            }
+
+            let (left, top) = (frame.left as u32, frame.top as u32);
+            let x_end = (left + width).min(self.canvas.width());
+            let y_end = (top + height).min(self.canvas.height());
+            for y in top..y_end {
+                for x in left..x_end {
+                    let offset = (((y - top) * width + (x - left)) * 4) as usize;
+                    let pixel = &rgba[offset..offset + 4];
+                    if pixel[3] != 0 {
+                        self.canvas.put_pixel(
+                            x,
+                            y,
+                            image::Rgba([pixel[0], pixel[1], pixel[2], pixel[3]]),
+                        );
+                    }
+                }
+            }
+
+            self.pending = Some((frame.dispose, left, top, width, height));
+            Ok(self.canvas.clone())
+        }
+
+        /// Applies the disposal method of the previously drawn frame
+        fn dispose_previous(&mut self) {
+            match self.pending.take() {
+                Some((DisposalMethod::Background, left, top, width, height)) => {
+                    // Browsers clear to transparent rather than the background colour
+                    let x_end = (left + width).min(self.canvas.width());
+                    let y_end = (top + height).min(self.canvas.height());
+                    for y in top..y_end {
+                        for x in left..x_end {
+                            self.canvas.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+                        }
+                    }
+                }
+                Some((DisposalMethod::Previous, ..)) => {
+                    if let Some(saved) = self.saved.take() {
+                        self.canvas = saved;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A time range of an animation, open-ended when `end` is `None`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TrimRange {
+        pub start: Duration,
+        pub end: Option<Duration>,
+    }
+
+    impl FromStr for TrimRange {
+        type Err = GridistError;
+
+        /// Parses `START-END`, where each side is `[[h:]m:]s[.frac]` and
+        /// either side may be left empty, e.g. `0:00-0:05` or `1.5-`
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || GridistError::InvalidTiming(format!("Invalid trim range '{}'", s));
+            let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+            let start = match start.trim() {
+                "" => Duration::ZERO,
+                start => parse_timestamp(start).ok_or_else(invalid)?,
+            };
+            let end = match end.trim() {
+                "" => None,
+                end => Some(parse_timestamp(end).ok_or_else(invalid)?),
+            };
+            if end.is_some_and(|end| end <= start) {
+                return Err(GridistError::InvalidTiming(format!(
+                    "Trim range '{}' ends before it starts",
+                    s
+                )));
+            }
+            Ok(Self { start, end })
+        }
+    }
+
+    /// Parses `[[h:]m:]s[.frac]` into a duration
+    fn parse_timestamp(s: &str) -> Option<Duration> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+        let (seconds, units) = parts.split_last()?;
+        let seconds: f64 = seconds.parse().ok()?;
+        let minutes = units.iter().try_fold(0u64, |total, unit| {
+            Some(total * 60 + unit.parse::<u64>().ok()?)
+        })?;
+        (seconds.is_finite() && seconds >= 0.0)
+            .then(|| Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+    }
+
+    /// Playback adjustments applied to animated frames before tiling
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Timing {
+        /// Highest frame rate kept; faster frames are merged into the previous one
+        pub max_fps: Option<f32>,
+        /// Part of the animation to keep, in source time
+        pub trim: Option<TrimRange>,
+        /// Highest number of frames kept, dropped evenly over the animation
+        pub max_frames: Option<usize>,
+        /// Playback speed factor, 2.0 plays twice as fast
+        pub speed: f32,
+    }
+
+    impl Default for Timing {
+        fn default() -> Self {
+            Self {
+                max_fps: None,
+                trim: None,
+                max_frames: None,
+                speed: 1.0,
+            }
+        }
+    }
+
+    impl Timing {
+        /// Checks that the factors are positive and the limits non-zero
+        pub fn validate(&self) -> GridistResult<()> {
+            if !(self.speed.is_finite() && self.speed > 0.0) {
+                return Err(GridistError::InvalidTiming(format!(
+                    "Speed must be positive, got {}",
+                    self.speed
+                )));
+            }
+            if let Some(fps) = self.max_fps.filter(|fps| !(fps.is_finite() && *fps > 0.0)) {
+                return Err(GridistError::InvalidTiming(format!(
+                    "Maximum FPS must be positive, got {}",
+                    fps
+                )));
+            }
+            if self.max_frames == Some(0) {
+                return Err(GridistError::InvalidTiming(
+                    "Maximum frame count must be at least 1".to_string(),
+                ));
+            }
+            Ok(())
+        }
+
+        /// Picks the frames to keep and their new delays
+        ///
+        /// `delays` are the source frame delays in hundredths of a second.
+        /// Returns `(source index, delay)` pairs in playback order. Delays of
+        /// dropped frames are added to the frame kept before them, and timestamps
+        /// are rounded once so the total duration doesn't drift.
+        pub fn schedule(&self, delays: &[u16]) -> GridistResult<Vec<(usize, u16)>> {
+            let mut starts = Vec::with_capacity(delays.len());
+            let mut time = 0.0;
+            for &delay in delays {
+                starts.push(time);
+                time += delay as f64;
+            }
+
+            // Trim in source time, clipping the frames that straddle the range
+            let (trim_start, trim_end) = match self.trim {
+                Some(trim) => (
+                    trim.start.as_secs_f64() * 100.0,
+                    trim.end.map_or(time, |end| end.as_secs_f64() * 100.0),
+                ),
+                None => (0.0, time),
+            };
+            let mut frames: Vec<(usize, f64)> = starts
+                .iter()
+                .enumerate()
+                .filter(|&(i, &start)| {
+                    let end = start + delays[i] as f64;
+                    // Zero-length frames at the end of the range still belong to it
+                    let before_end = start < trim_end || (delays[i] == 0 && start == trim_end);
+                    before_end && (end > trim_start || start == trim_start)
+                })
+                .map(|(i, &start)| (i, start.max(trim_start) - trim_start))
+                .collect();
+            if frames.is_empty() {
+                return Err(GridistError::InvalidTiming(
+                    "Trim range contains no frames".to_string(),
+                ));
+            }
+            let duration = (trim_end.min(time) - trim_start) / self.speed as f64;
+            for frame in &mut frames {
+                frame.1 /= self.speed as f64;
+            }
+
+            if let Some(fps) = self.max_fps {
+                let interval = 100.0 / fps as f64;
+                let mut next = 0.0;
+                frames.retain(|&(_, start)| {
+                    let keep = start >= next;
+                    if keep {
+                        next = start + interval;
+                    }
+                    keep
+                });
+            }
+
+            if let Some(max_frames) = self.max_frames.filter(|&max| max < frames.len()) {
+                let step = frames.len() as f64 / max_frames as f64;
+                frames = (0..max_frames)
+                    .map(|i| frames[(i as f64 * step) as usize])
+                    .collect();
+            }
+
+            let ends = frames
+                .iter()
+                .skip(1)
+                .map(|&(_, start)| start)
+                .chain([duration]);
+            Ok(frames
+                .iter()
+                .zip(ends)
+                .map(|(&(index, start), end)| {
+                    let delay = end.round() - start.round();
+                    (index, delay.clamp(0.0, u16::MAX as f64) as u16)
+                })
+                .collect())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+        /// A frame of a hand-built GIF: position, size, palette index and disposal
+        struct TestFrame {
+            left: u16,
+            top: u16,
+            width: u16,
+            height: u16,
+            index: u8,
+            dispose: DisposalMethod,
+        }
+
+        fn frame(rect: (u16, u16, u16, u16), index: u8, dispose: DisposalMethod) -> TestFrame {
+            let (left, top, width, height) = rect;
+            TestFrame {
+                left,
+                top,
+                width,
+                height,
+                index,
+                dispose,
+            }
+        }
+
+        /// Encodes a 4x4 GIF with red, green and blue at indices 0-2 and a
+        /// transparent index 3, then composites every frame
+        fn composite(frames: &[TestFrame]) -> Vec<RgbaImage> {
+            let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0];
+            let mut bytes = Vec::new();
+            {
+                let mut encoder = Encoder::new(&mut bytes, 4, 4, &palette).unwrap();
+                for test in frames {
+                    let pixels = vec![test.index; test.width as usize * test.height as usize];
+                    let mut frame =
+                        Frame::from_indexed_pixels(test.width, test.height, pixels, Some(3));
+                    frame.left = test.left;
+                    frame.top = test.top;
+                    frame.dispose = test.dispose;
+                    encoder.write_frame(&frame).unwrap();
+                }
+            }
+
+            let mut options = gif::DecodeOptions::new();
+            options.set_color_output(gif::ColorOutput::RGBA);
+            let mut decoder = options.read_info(Cursor::new(bytes)).unwrap();
+            let mut compositor = GifCompositor::new(4, 4);
+            let mut canvases = Vec::new();
+            while let Some(frame) = decoder.read_next_frame().unwrap() {
+                canvases.push(compositor.composite(frame, &frame.buffer).unwrap());
+            }
+            canvases
+        }
+
+        /// Checks every pixel, expecting `inside` within the rectangle and `outside` elsewhere
+        fn assert_rect(
+            canvas: &RgbaImage,
+            (left, top, width, height): (u32, u32, u32, u32),
+            inside: [u8; 4],
+            outside: [u8; 4],
+        ) {
+            for (x, y, pixel) in canvas.enumerate_pixels() {
+                let within = (left..left + width).contains(&x) && (top..top + height).contains(&y);
+                let expected = if within { inside } else { outside };
+                assert_eq!(pixel.0, expected, "pixel ({}, {})", x, y);
+            }
+        }
+
+        #[test]
+        fn composite_keeps_sub_rect_frames_on_the_canvas() {
+            let canvases = composite(&[
+                frame((0, 0, 4, 4), 0, DisposalMethod::Keep),
+                frame((1, 1, 2, 2), 1, DisposalMethod::Keep),
+                frame((0, 0, 4, 1), 3, DisposalMethod::Keep),
+            ]);
+            assert_rect(&canvases[0], (0, 0, 4, 4), RED, RED);
+            assert_rect(&canvases[1], (1, 1, 2, 2), GREEN, RED);
+            // A fully transparent frame leaves the canvas as it was
+            assert_rect(&canvases[2], (1, 1, 2, 2), GREEN, RED);
+        }
+
+        #[test]
+        fn composite_clears_background_disposal_to_transparent() {
+            let canvases = composite(&[
+                frame((0, 0, 4, 4), 0, DisposalMethod::Keep),
+                frame((1, 1, 2, 2), 1, DisposalMethod::Background),
+                frame((3, 3, 1, 1), 2, DisposalMethod::Keep),
+            ]);
+            assert_rect(&canvases[1], (1, 1, 2, 2), GREEN, RED);
+            let last = &canvases[2];
+            for (x, y, pixel) in last.enumerate_pixels() {
+                let expected = match (x, y) {
+                    (1..=2, 1..=2) => CLEAR,
+                    (3, 3) => BLUE,
+                    _ => RED,
+                };
+                assert_eq!(pixel.0, expected, "pixel ({}, {})", x, y);
+            }
+        }
+
+        #[test]
+        fn composite_restores_the_canvas_for_previous_disposal() {
+            let canvases = composite(&[
+                frame((0, 0, 4, 4), 0, DisposalMethod::Keep),
+                frame((0, 0, 2, 4), 1, DisposalMethod::Previous),
+                frame((2, 0, 2, 2), 2, DisposalMethod::Previous),
+                frame((0, 3, 1, 1), 1, DisposalMethod::Keep),
+            ]);
+            assert_rect(&canvases[1], (0, 0, 2, 4), GREEN, RED);
+            // The green half is undone before the blue corner is drawn
+            assert_rect(&canvases[2], (2, 0, 2, 2), BLUE, RED);
+            assert_rect(&canvases[3], (0, 3, 1, 1), GREEN, RED);
+        }
+
+        #[test]
+        fn composite_starts_from_a_transparent_canvas() {
+            let canvases = composite(&[frame((2, 1, 1, 2), 2, DisposalMethod::Keep)]);
+            assert_rect(&canvases[0], (2, 1, 1, 2), BLUE, CLEAR);
+        }
+
+        #[test]
+        fn schedule_keeps_zero_delay_frames() {
+            let timing = Timing::default();
+            assert_eq!(timing.schedule(&[0]).unwrap(), vec![(0, 0)]);
+            assert_eq!(
+                timing.schedule(&[0, 0, 0]).unwrap(),
+                vec![(0, 0), (1, 0), (2, 0)]
+            );
+            assert_eq!(timing.schedule(&[10, 0]).unwrap(), vec![(0, 10), (1, 0)]);
+        }
+
+        #[test]
+        fn schedule_keeps_zero_delay_frames_at_trim_end() {
+            let timing = Timing {
+                trim: Some(TrimRange {
+                    start: Duration::ZERO,
+                    end: Some(Duration::from_millis(100)),
+                }),
+                ..Timing::default()
+            };
+            assert_eq!(
+                timing.schedule(&[10, 0, 10]).unwrap(),
+                vec![(0, 10), (1, 0)]
+            );
+        }
+
+        #[test]
+        fn schedule_merges_frames_dropped_by_max_fps() {
+            let timing = Timing {
+                max_fps: Some(25.0),
+                ..Timing::default()
+            };
+            assert_eq!(
+                timing.schedule(&[2; 6]).unwrap(),
+                vec![(0, 4), (2, 4), (4, 4)]
+            );
+        }
+
+        #[test]
+        fn schedule_clips_frames_to_the_trim_range() {
+            let trim = |start: u64, end: Option<u64>| Timing {
+                trim: Some(TrimRange {
+                    start: Duration::from_millis(start),
+                    end: end.map(Duration::from_millis),
+                }),
+                ..Timing::default()
+            };
+            // The first kept frame starts before the range and is shortened
+            assert_eq!(
+                trim(150, Some(350)).schedule(&[10; 4]).unwrap(),
+                vec![(1, 5), (2, 10), (3, 5)]
+            );
+            assert_eq!(
+                trim(200, None).schedule(&[10; 4]).unwrap(),
+                vec![(2, 10), (3, 10)]
+            );
+            assert!(matches!(
+                trim(1000, None).schedule(&[10; 4]),
+                Err(GridistError::InvalidTiming(_))
+            ));
+        }
+
+        #[test]
+        fn schedule_keeps_the_duration_when_dropping_frames() {
+            let timing = Timing {
+                max_frames: Some(4),
+                ..Timing::default()
+            };
+            let frames = timing.schedule(&[10; 10]).unwrap();
+            assert_eq!(frames, vec![(0, 20), (2, 30), (5, 20), (7, 30)]);
+            assert_eq!(frames.iter().map(|&(_, delay)| delay).sum::<u16>(), 100);
+        }
+
+        #[test]
+        fn schedule_scales_delays_by_speed() {
+            let speed = |speed| Timing {
+                speed,
+                ..Timing::default()
+            };
+            assert_eq!(
+                speed(2.0).schedule(&[10, 30]).unwrap(),
+                vec![(0, 5), (1, 15)]
+            );
+            // Slowed-down delays saturate at the largest GIF delay
+            assert_eq!(
+                speed(0.5).schedule(&[60000, 10]).unwrap(),
+                vec![(0, u16::MAX), (1, 20)]
+            );
+        }
+
+        /// Encodes a 2x2 RGBA PNG, as an APNG of two frames when `animated`
+        fn png_bytes(animated: bool) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let frames = if animated { 2 } else { 1 };
+            if animated {
+                encoder.set_animated(frames, 0).unwrap();
+            }
+            let mut writer = encoder.write_header().unwrap();
+            for _ in 0..frames {
+                writer
+                    .write_image_data(&[RED, GREEN, BLUE, CLEAR].concat())
+                    .unwrap();
+            }
+            writer.finish().unwrap();
+            bytes
+        }
+
+        #[test]
+        fn is_animated_finds_the_actl_chunk_of_apngs() {
+            let apng = png_bytes(true);
+            assert_eq!(cropper::detect_format(&apng).unwrap(), ImageFormat::Png);
+            assert!(is_animated(&apng, ImageFormat::Png));
+            assert!(!is_animated(&png_bytes(false), ImageFormat::Png));
+        }
+
+        #[test]
+        fn is_animated_depends_on_the_format() {
+            let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, image::Rgba(RED)));
+            for (format, animated) in [
+                (ImageFormat::Gif, true),
+                (ImageFormat::WebP, false),
+                (ImageFormat::Jpeg, false),
+            ] {
+                let image = if format == ImageFormat::Jpeg {
+                    DynamicImage::ImageRgb8(image.to_rgb8())
+                } else {
+                    image.clone()
+                };
+                let mut bytes = Vec::new();
+                image
+                    .write_to(&mut Cursor::new(&mut bytes), format)
+                    .unwrap();
+                assert_eq!(cropper::detect_format(&bytes).unwrap(), format);
+                assert_eq!(is_animated(&bytes, format), animated, "{:?}", format);
+            }
+        }
+    }
+}
+
+/// Colour quantization for GIF output
+pub mod quantize {
+    use crate::simd::PaletteLanes;
+    use clap::ValueEnum;
+    use image::RgbaImage;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    /// Upper bound on the pixels sampled to build a palette
+    const MAX_SAMPLES: usize = 1 << 20;
+
+    /// How the palette of GIF tiles is chosen
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+    pub enum Quantizer {
+        /// Keep the source palette, quantizing globally when frames use several
+        #[default]
+        Source,
+        /// Build one median-cut palette shared by every tile
+        Global,
+        /// Build a separate median-cut palette for each tile
+        PerTile,
+    }
+
+    /// A colour weighted by how many sampled pixels use it
+    #[derive(Clone, Copy)]
+    struct WeightedColor {
+        color: [u8; 3],
+        count: u32,
+    }
+
+    /// Collects the opaque colours of a set of images
+    ///
+    /// Large inputs are sampled with a fixed stride so the result stays
+    /// deterministic while bounding the work done by the quantizer.
+    pub fn sample_colors(images: &[&RgbaImage]) -> Vec<[u8; 3]> {
+        let total: usize = images.iter().map(|image| image.as_raw().len() / 4).sum();
+        let stride = total.div_ceil(MAX_SAMPLES).max(1);
+
+        images
+            .iter()
+            .flat_map(|image| image.as_raw().chunks_exact(4).step_by(stride))
+            .filter(|pixel| pixel[3] >= 128)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect()
+    }
+
+    /// Builds a palette of at most `max_colors` colours with median cut
+    ///
+    /// The box with the widest channel range, weighted by its pixel count,
+    /// is split at its weighted median until enough boxes exist. Each box
+    /// contributes the weighted mean of its colours.
+    pub fn median_cut(colors: &[[u8; 3]], max_colors: usize) -> Vec<u8> {
+        let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+        for &color in colors {
+            *histogram.entry(color).or_insert(0) += 1;
+        }
+        let mut unique: Vec<_> = histogram
+            .into_iter()
+            .map(|(color, count)| WeightedColor { color, count })
+            .collect();
+        // HashMap iteration order is random; sort so the palette is reproducible
+        unique.sort_unstable_by_key(|c| c.color);
+
+        if unique.is_empty() {
+            return vec![0, 0, 0];
+        }
+
+        let mut boxes = vec![unique];
+        while boxes.len() < max_colors.max(1) {
+            let candidate = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.len() > 1)
+                .max_by_key(|(_, b)| {
+                    let (_, range) = widest_channel(b);
+                    range as u64 * b.iter().map(|c| c.count as u64).sum::<u64>()
+                })
+                .map(|(index, _)| index);
+            let Some(index) = candidate else {
+                break;
+            };
+
+            let mut current = boxes.swap_remove(index);
+            let (channel, _) = widest_channel(&current);
+            current.sort_unstable_by_key(|c| (c.color[channel], c.color));
+
+            let total: u64 = current.iter().map(|c| c.count as u64).sum();
+            let mut running = 0;
+            let mut split = 1;
+            for (i, c) in current.iter().enumerate() {
+                running += c.count as u64;
+                if running * 2 >= total {
+                    split = (i + 1).clamp(1, current.len() - 1);
+                    break;
+                }
+            }
+            let upper = current.split_off(split);
+            boxes.push(current);
+            boxes.push(upper);
+        }
+
+        boxes
+            .iter()
+            .flat_map(|b| {
+                let total: u64 = b.iter().map(|c| c.count as u64).sum();
+                let mut sums = [0u64; 3];
+                for c in b {
+                    for (sum, &value) in sums.iter_mut().zip(&c.color) {
+                        *sum += value as u64 * c.count as u64;
+                    }
+                }
+                sums.map(|sum| ((sum + total / 2) / total) as u8)
+            })
+            .collect()
+    }
+
+    /// Returns the channel with the largest value range in a box, and that range
+    fn widest_channel(colors: &[WeightedColor]) -> (usize, u8) {
+        (0..3)
+            .map(|channel| {
+                let (min, max) = colors.iter().fold((u8::MAX, u8::MIN), |(min, max), c| {
+                    (min.min(c.color[channel]), max.max(c.color[channel]))
+                });
+                (channel, max - min)
+            })
+            .max_by_key(|&(channel, range)| (range, std::cmp::Reverse(channel)))
+            .unwrap_or((0, 0))
+    }
+
+    /// Returns whether any pixel of the images has alpha below 128
+    pub fn has_transparency(images: &[&RgbaImage]) -> bool {
+        images
+            .iter()
+            .any(|image| image.as_raw().chunks_exact(4).any(|pixel| pixel[3] < 128))
+    }
+
+    /// Appends a black entry for transparent pixels and returns its index
+    ///
+    /// The palette must hold fewer than 256 colours.
+    pub fn push_transparent_slot(palette: &mut Vec<u8>) -> u8 {
+        let index = palette.len() / 3;
+        palette.truncate(index * 3);
+        palette.extend_from_slice(&[0, 0, 0]);
+        index as u8
+    }
+
+    /// Bits of each channel used to index the lookup cube
+    const CUBE_BITS: u32 = 5;
+
+    /// Cells with more candidates than this scan the whole palette in lanes
+    const MAX_SCALAR_CANDIDATES: usize = 16;
+
+    /// Nearest-colour lookup for a palette, built once and shared
+    ///
+    /// Colours that are exactly in the palette resolve through a hash map.
+    /// Everything else goes through a 32x32x32 cube whose cells list every
+    /// palette entry that can be nearest to some colour inside them, so a
+    /// lookup only compares a handful of candidates and stays exact. Cells
+    /// are filled the first time a colour lands in them. The transparent
+    /// slot, if any, is never returned for a colour.
+    #[derive(Clone)]
+    pub struct PaletteLookup {
+        palette: Vec<u8>,
+        transparent: Option<u8>,
+        exact: HashMap<[u8; 3], u8>,
+        entries: Vec<(u8, [i32; 3])>,
+        cells: Vec<OnceLock<Box<[u8]>>>,
+        lanes: PaletteLanes,
+    }
+
+    impl PaletteLookup {
+        /// Builds the lookup for a flat RGB palette of up to 256 entries,
+        /// with `transparent` reserved for transparent pixels
+        pub fn new(palette: &[u8], transparent: Option<u8>) -> Self {
+            let colors: Vec<[u8; 3]> = palette
+                .chunks_exact(3)
+                .take(256)
+                .map(|c| [c[0], c[1], c[2]])
+                .collect();
+
+            let mut exact = HashMap::with_capacity(colors.len());
+            for (index, &color) in colors.iter().enumerate() {
+                if Some(index as u8) == transparent {
+                    continue;
+                }
+                // Keep the first index of duplicated entries
+                exact.entry(color).or_insert(index as u8);
+            }
+
+            let entries: Vec<(u8, [i32; 3])> = colors
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| Some(index as u8) != transparent)
+                .map(|(index, color)| (index as u8, color.map(i32::from)))
+                .collect();
+
+            Self {
+                palette: palette.to_vec(),
+                transparent,
+                exact,
+                entries,
+                cells: (0..1 << (3 * CUBE_BITS)).map(|_| OnceLock::new()).collect(),
+                lanes: PaletteLanes::new(&colors, transparent),
+            }
+        }
+
+        /// Quantizes `images` to at most `max_colors` entries with median cut,
+        /// reserving one of them for transparency when any pixel has alpha
+        /// below 128
+        pub fn quantized(images: &[&RgbaImage], max_colors: usize) -> Self {
+            let transparency = has_transparency(images);
+            let max_colors = max_colors.clamp(2, 256) - transparency as usize;
+            let mut palette = median_cut(&sample_colors(images), max_colors);
+            let transparent = transparency.then(|| push_transparent_slot(&mut palette));
+            Self::new(&palette, transparent)
+        }
+
+        /// Returns the palette this lookup was built for
+        pub fn palette(&self) -> &[u8] {
+            &self.palette
+        }
+
+        /// Returns the index reserved for transparent pixels
+        pub fn transparent(&self) -> Option<u8> {
+            self.transparent
+        }
+
+        /// Returns the index for an RGBA pixel, using the transparent slot
+        /// for alpha below 128 when the palette has one
+        pub fn index(&self, pixel: [u8; 4]) -> u8 {
+            match self.transparent {
+                Some(transparent) if pixel[3] < 128 => transparent,
+                _ => self.nearest([pixel[0], pixel[1], pixel[2]]),
+            }
+        }
+
+        /// Returns the index of the palette colour nearest to `color`
+        pub fn nearest(&self, color: [u8; 3]) -> u8 {
+            if let Some(&index) = self.exact.get(&color) {
+                return index;
+            }
+            let shift = 8 - CUBE_BITS;
+            let [r, g, b] = color.map(|c| (c >> shift) as usize);
+            let bin = (r << (2 * CUBE_BITS)) | (g << CUBE_BITS) | b;
+            let candidates = self.cells[bin].get_or_init(|| {
+                let low = [r, g, b].map(|c| (c << shift) as i32);
+                cell_candidates(&self.entries, low, low.map(|c| c + (1 << shift) - 1))
+            });
+            if candidates.len() > MAX_SCALAR_CANDIDATES {
+                return self.lanes.nearest(color);
+            }
+            let color = color.map(i32::from);
+            // Candidates are in palette order, so the lowest index wins ties
+            candidates
+                .iter()
+                .copied()
+                .min_by_key(|&index| {
+                    let start = index as usize * 3;
+                    let entry = &self.palette[start..start + 3];
+                    (0..3)
+                        .map(|c| (entry[c] as i32 - color[c]).pow(2))
+                        .sum::<i32>()
+                })
+                .unwrap_or(0)
+        }
+    }
+
+    /// Returns the palette entries that can be nearest to some colour in the
+    /// box from `low` to `high`, inclusive
+    ///
+    /// No colour in the box is further from its nearest entry than the
+    /// smallest worst-case distance of any entry, so entries whose closest
+    /// point of the box is further than that can be skipped.
+    fn cell_candidates(entries: &[(u8, [i32; 3])], low: [i32; 3], high: [i32; 3]) -> Box<[u8]> {
+        let distance = |color: &[i32; 3], nearest: bool| -> i32 {
+            (0..3)
+                .map(|c| {
+                    let d = if nearest {
+                        (low[c] - color[c]).max(color[c] - high[c]).max(0)
+                    } else {
+                        (color[c] - low[c]).max(high[c] - color[c])
+                    };
+                    d * d
+                })
+                .sum()
+        };
+        let bound = entries
+            .iter()
+            .map(|(_, color)| distance(color, false))
+            .min()
+            .unwrap_or(0);
+        entries
+            .iter()
+            .filter(|(_, color)| distance(color, true) <= bound)
+            .map(|&(index, _)| index)
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Nearest palette index by scanning every entry, lowest index on ties
+        fn brute_force(palette: &[u8], transparent: Option<u8>, color: [u8; 3]) -> u8 {
+            palette
+                .chunks_exact(3)
+                .enumerate()
+                .filter(|&(index, _)| Some(index as u8) != transparent)
+                .min_by_key(|(_, entry)| {
+                    (0..3)
+                        .map(|c| (entry[c] as i32 - color[c] as i32).pow(2))
+                        .sum::<i32>()
+                })
+                .map_or(0, |(index, _)| index as u8)
+        }
+
+        #[test]
+        fn lookup_matches_brute_force_nearest() {
+            let mut state = 0x2545_f491_u32;
+            let mut next = move || {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            };
+            let mut palette: Vec<u8> = (0..255 * 3).map(|_| next() as u8).collect();
+            let transparent = Some(push_transparent_slot(&mut palette));
+            let lookup = PaletteLookup::new(&palette, transparent);
+            for _ in 0..20_000 {
+                let color = next().to_le_bytes();
+                let color = [color[0], color[1], color[2]];
+                assert_eq!(
+                    lookup.nearest(color),
+                    brute_force(&palette, transparent, color),
+                    "{color:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn lookup_keeps_every_entry_of_a_grey_ramp() {
+            let palette: Vec<u8> = (0..=255u8).flat_map(|v| [v, v, v]).collect();
+            let lookup = PaletteLookup::new(&palette, None);
+            for v in 0..=255u8 {
+                let color = [v, v.saturating_add(1), v];
+                assert_eq!(lookup.nearest(color), brute_force(&palette, None, color));
+                assert_eq!(lookup.nearest(color), v);
+            }
+        }
+    }
+}
+
+/// Dithering for GIF palette mapping
+///
+/// Every frame of a tile is dithered on its own, sequentially, so the result
+/// does not depend on how rayon schedules frames and tiles.
+pub mod dither {
+    use crate::quantize::PaletteLookup;
+    use clap::ValueEnum;
+    use image::RgbaImage;
+
+    /// 8x8 Bayer threshold matrix
+    const BAYER_8X8: [[u8; 8]; 8] = [
+        [0, 32, 8, 40, 2, 34, 10, 42],
+        [48, 16, 56, 24, 50, 18, 58, 26],
+        [12, 44, 4, 36, 14, 46, 6, 38],
+        [60, 28, 52, 20, 62, 30, 54, 22],
+        [3, 35, 11, 43, 1, 33, 9, 41],
+        [51, 19, 59, 27, 49, 17, 57, 25],
+        [15, 47, 7, 39, 13, 45, 5, 37],
+        [63, 31, 55, 23, 61, 29, 53, 21],
+    ];
+
+    /// Strength of the ordered dithering offsets, in colour levels
+    const ORDERED_SPREAD: f32 = 32.0;
+
+    /// Floyd–Steinberg error distribution as (dx, dy, weight / 16)
+    const FLOYD_STEINBERG: &[(i32, i32, f32)] = &[
+        (1, 0, 7.0 / 16.0),
+        (-1, 1, 3.0 / 16.0),
+        (0, 1, 5.0 / 16.0),
+        (1, 1, 1.0 / 16.0),
+    ];
+
+    /// Atkinson error distribution, which only spreads 6/8 of the error
+    const ATKINSON: &[(i32, i32, f32)] = &[
+        (1, 0, 1.0 / 8.0),
+        (2, 0, 1.0 / 8.0),
+        (-1, 1, 1.0 / 8.0),
+        (0, 1, 1.0 / 8.0),
+        (1, 1, 1.0 / 8.0),
+        (0, 2, 1.0 / 8.0),
+    ];
+
+    /// Dithering method used when mapping pixels to palette colours
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+    pub enum Dither {
+        /// Map every pixel to its nearest palette colour
+        #[default]
+        None,
+        /// Floyd–Steinberg error diffusion
+        FloydSteinberg,
+        /// Atkinson error diffusion, with less bleeding on flat areas
+        Atkinson,
+        /// Ordered dithering with an 8x8 Bayer matrix
+        Ordered,
+    }
+
+    /// Maps an image to palette indices using the given dithering method
+    ///
+    /// `origin` is the image's position in the full resized frame; ordered
+    /// dithering uses it so the pattern lines up across tiles. Pixels with
+    /// alpha below 128 take the palette's transparent slot, when it has one,
+    /// and don't diffuse any error.
+    pub fn dither_image(
+        image: &RgbaImage,
+        origin: (u32, u32),
+        method: Dither,
+        lookup: &PaletteLookup,
+    ) -> Vec<u8> {
+        match method {
+            Dither::None => image.pixels().map(|p| lookup.index(p.0)).collect(),
+            Dither::Ordered => ordered(image, origin, lookup),
+            Dither::FloydSteinberg => diffuse(image, FLOYD_STEINBERG, lookup),
+            Dither::Atkinson => diffuse(image, ATKINSON, lookup),
+        }
+    }
+
+    /// Ordered dithering using absolute coordinates for the threshold matrix
+    fn ordered(image: &RgbaImage, origin: (u32, u32), lookup: &PaletteLookup) -> Vec<u8> {
+        image
+            .enumerate_pixels()
+            .map(|(x, y, p)| {
+                if let (Some(transparent), true) = (lookup.transparent(), p[3] < 128) {
+                    return transparent;
+                }
+                let (ax, ay) = ((origin.0 + x) as usize, (origin.1 + y) as usize);
+                let threshold = (BAYER_8X8[ay % 8][ax % 8] as f32 + 0.5) / 64.0 - 0.5;
+                let offset = threshold * ORDERED_SPREAD;
+                lookup.nearest(
+                    [p[0], p[1], p[2]].map(|c| (c as f32 + offset).clamp(0.0, 255.0) as u8),
+                )
+            })
+            .collect()
+    }
+
+    /// Error diffusion dithering with the given distribution kernel
+    fn diffuse(image: &RgbaImage, kernel: &[(i32, i32, f32)], lookup: &PaletteLookup) -> Vec<u8> {
+        let (width, height) = (image.width() as i32, image.height() as i32);
+        let mut errors = vec![[0f32; 3]; (width * height) as usize];
+        let mut indexed = Vec::with_capacity(errors.len());
+
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = image.get_pixel(x as u32, y as u32);
+                if let (Some(transparent), true) = (lookup.transparent(), pixel[3] < 128) {
+                    indexed.push(transparent);
+                    continue;
+                }
+
+                let error = errors[(y * width + x) as usize];
+                let wanted = [0, 1, 2].map(|c| (pixel[c] as f32 + error[c]).clamp(0.0, 255.0));
+                let index = lookup.nearest(wanted.map(|c| c.round() as u8));
+                indexed.push(index);
+
+                let offset = index as usize * 3;
+                let Some(chosen) = lookup.palette().get(offset..offset + 3) else {
+                    continue;
+                };
+                let residual = [0, 1, 2].map(|c| wanted[c] - chosen[c] as f32);
+                for &(dx, dy, weight) in kernel {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let target = &mut errors[(ny * width + nx) as usize];
+                    for c in 0..3 {
+                        target[c] += residual[c] * weight;
+                    }
+                }
+            }
+        }
+
+        indexed
+    }
+}
+
+/// SIMD helpers for the colour conversion hot paths
+///
+/// These work on eight lanes at a time with `wide`, which compiles to
+/// SSE/AVX or NEON where available and to plain scalar code elsewhere.
+/// Palette expansion is not here: `wide` has no gather, so it goes
+/// through a 256-entry table instead.
+pub mod simd {
+    use wide::{f32x8, i32x8, CmpLt};
+
+    /// Channel value padding unused palette lanes, far from any real colour
+    const PADDING: f32 = 4096.0;
+
+    /// Palette colours split per channel into groups of eight
+    ///
+    /// Distances use f32 lanes since 32-bit integer multiplies are not
+    /// available on baseline x86_64; all values involved are exact in f32.
+    #[derive(Clone)]
+    pub struct PaletteLanes {
+        red: Vec<f32x8>,
+        green: Vec<f32x8>,
+        blue: Vec<f32x8>,
+    }
+
+    impl PaletteLanes {
+        /// Splits `colors` into lanes, padding the last group and the
+        /// `excluded` entry so they are never the nearest
+        pub fn new(colors: &[[u8; 3]], excluded: Option<u8>) -> Self {
+            let channel = |c: usize| -> Vec<f32x8> {
+                colors
+                    .chunks(8)
+                    .enumerate()
+                    .map(|(group_index, group)| {
+                        let mut lanes = [PADDING; 8];
+                        for (i, (lane, color)) in lanes.iter_mut().zip(group).enumerate() {
+                            if excluded != Some((group_index * 8 + i) as u8) {
+                                *lane = color[c] as f32;
+                            }
+                        }
+                        f32x8::new(lanes)
+                    })
+                    .collect()
+            };
+            Self {
+                red: channel(0),
+                green: channel(1),
+                blue: channel(2),
+            }
+        }
+
+        /// Returns the index of the nearest colour by squared euclidean
+        /// distance, preferring the lowest index on ties
+        pub fn nearest(&self, color: [u8; 3]) -> u8 {
+            let [r, g, b] = color.map(|c| f32x8::splat(c as f32));
+            let mut best = f32x8::splat(f32::MAX);
+            let mut best_index = f32x8::splat(0.0);
+            let mut index = f32x8::new([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+
+            for ((red, green), blue) in self.red.iter().zip(&self.green).zip(&self.blue) {
+                let (dr, dg, db) = (*red - r, *green - g, *blue - b);
+                let distance = dr * dr + dg * dg + db * db;
+                // Strictly closer only, so earlier entries win ties per lane
+                let closer = distance.cmp_lt(best);
+                best = closer.blend(distance, best);
+                best_index = closer.blend(index, best_index);
+                index += f32x8::splat(8.0);
+            }
+
+            best.to_array()
+                .into_iter()
+                .zip(best_index.to_array())
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                .map_or(0, |(_, index)| index as u8)
+        }
+    }
+
+    /// Returns a bit mask of the pixels in up to eight RGBA pixels whose
+    /// alpha is at least 128
+    pub fn opaque_mask(pixels: &[u8]) -> u8 {
+        match <&[u8; 32]>::try_from(pixels) {
+            Ok(pixels) => {
+                // One 32-byte load; alpha is the top byte of each little-endian
+                // word, so alpha >= 128 sets its sign bit
+                let words: i32x8 = bytemuck::cast(*pixels);
+                #[cfg(target_endian = "big")]
+                let words = i32x8::new(words.to_array().map(i32::swap_bytes));
+                words.move_mask() as u8
+            }
+            Err(_) => pixels
+                .chunks_exact(4)
+                .take(8)
+                .enumerate()
+                .filter(|(_, pixel)| pixel[3] >= 128)
+                .fold(0, |mask, (i, _)| mask | 1 << i),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn opaque_mask_matches_alpha_threshold() {
+            let alphas = [0u8, 127, 128, 255, 200, 1, 129, 64];
+            let pixels: Vec<u8> = alphas
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &alpha)| [255, i as u8, 128, alpha])
+                .collect();
+            assert_eq!(opaque_mask(&pixels), 0b0101_1100);
+            // Tails shorter than eight pixels take the scalar path
+            assert_eq!(opaque_mask(&pixels[..12]), 0b0000_0100);
+        }
+    }
+}
+
+/// Edge-based saliency for placing the crop
+///
+/// Detail is measured as the Sobel gradient magnitude of the luma, with
+/// transparent pixels counting as flat. Summed-area tables make the total
+/// of any rectangle a constant-time lookup, so every crop offset can be
+/// scored.
+pub mod saliency {
+    use image::RgbaImage;
+
+    /// Per-pixel detail of an image, or the sum over several frames
+    #[derive(Debug, Clone)]
+    pub struct SaliencyMap {
+        width: u32,
+        height: u32,
+        values: Vec<f32>,
+    }
+
+    impl SaliencyMap {
+        /// Builds the Sobel edge magnitude of an image
+        pub fn edges(image: &RgbaImage) -> Self {
+            let (width, height) = image.dimensions();
+            let luma: Vec<f32> = image
+                .pixels()
+                .map(|p| {
+                    let [r, g, b, a] = p.0.map(f32::from);
+                    (0.299 * r + 0.587 * g + 0.114 * b) * a / 255.0
+                })
+                .collect();
+
+            let (w, h) = (width as usize, height as usize);
+            let mut values = vec![0.0; w * h];
+            // Border pixels are clamped to their nearest neighbour
+            let at = |x: usize, y: usize, dx: isize, dy: isize| {
+                let x = (x as isize + dx).clamp(0, w as isize - 1) as usize;
+                let y = (y as isize + dy).clamp(0, h as isize - 1) as usize;
+                luma[y * w + x]
+            };
+            for y in 0..h {
+                for x in 0..w {
+                    let gx = at(x, y, 1, -1) + 2.0 * at(x, y, 1, 0) + at(x, y, 1, 1)
+                        - at(x, y, -1, -1)
+                        - 2.0 * at(x, y, -1, 0)
+                        - at(x, y, -1, 1);
+                    let gy = at(x, y, -1, 1) + 2.0 * at(x, y, 0, 1) + at(x, y, 1, 1)
+                        - at(x, y, -1, -1)
+                        - 2.0 * at(x, y, 0, -1)
+                        - at(x, y, 1, -1);
+                    values[y * w + x] = (gx * gx + gy * gy).sqrt();
+                }
+            }
+            Self {
+                width,
+                height,
+                values,
+            }
+        }
+
+        /// Returns the size of the map
+        pub fn dimensions(&self) -> (u32, u32) {
+            (self.width, self.height)
+        }
+
+        /// Adds the detail of another map of the same size
+        pub fn accumulate(&mut self, other: &SaliencyMap) {
+            debug_assert_eq!(self.dimensions(), other.dimensions());
+            for (value, other) in self.values.iter_mut().zip(&other.values) {
+                *value += other;
+            }
+        }
+
+        /// Builds the summed-area table of the map
+        pub fn summed_area(&self) -> SummedArea {
+            let stride = self.width as usize + 1;
+            let mut sums = vec![0.0f64; stride * (self.height as usize + 1)];
+            for (y, row) in self.values.chunks_exact(self.width as usize).enumerate() {
+                let mut running = 0.0;
+                for (x, &value) in row.iter().enumerate() {
+                    running += value as f64;
+                    sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + running;
+                }
+            }
+            SummedArea { stride, sums }
+        }
+    }
+
+    /// Summed-area table of a saliency map
+    pub struct SummedArea {
+        stride: usize,
+        sums: Vec<f64>,
+    }
+
+    impl SummedArea {
+        /// Returns the total detail inside a rectangle, which must lie in the map
+        pub fn sum(&self, x: u32, y: u32, width: u32, height: u32) -> f64 {
+            let (x0, y0) = (x as usize, y as usize);
+            let (x1, y1) = (x0 + width as usize, y0 + height as usize);
+            let s = self.stride;
+            self.sums[y1 * s + x1] - self.sums[y0 * s + x1] - self.sums[y1 * s + x0]
+                + self.sums[y0 * s + x0]
+        }
+
+        /// Returns the total detail of the whole map
+        pub fn total(&self) -> f64 {
+            self.sums.last().copied().unwrap_or(0.0)
+        }
+    }
+}
+
+/// File-size budget for tiles
+///
+/// Gists stop rendering large images, so tiles over the budget are
+/// re-encoded with fewer colours, fewer frames or a lower quality.
+pub mod budget {
+    use super::*;
+    use std::fmt;
+    use std::str::FromStr;
+
+    /// A size in bytes, written like `2MB`, `500KiB` or `1048576`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct ByteSize(pub u64);
+
+    impl FromStr for ByteSize {
+        type Err = GridistError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || GridistError::InvalidSize(format!("'{}' is not a size", s));
+            let s = s.trim();
+            let split = s
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(s.len());
+            let (number, unit) = s.split_at(split);
+            let number: f64 = number.parse().map_err(|_| invalid())?;
+            let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+                "" | "b" => 1.0,
+                "k" | "kb" => 1e3,
+                "m" | "mb" => 1e6,
+                "g" | "gb" => 1e9,
+                "kib" => 1024.0,
+                "mib" => 1024.0 * 1024.0,
+                "gib" => 1024.0 * 1024.0 * 1024.0,
+                _ => return Err(invalid()),
+            };
+            let bytes = (number * multiplier).round();
+            if bytes < 1.0 || bytes > u64::MAX as f64 {
+                return Err(GridistError::InvalidSize(format!(
+                    "'{}' must be at least one byte",
+                    s
+                )));
+            }
+            Ok(Self(bytes as u64))
+        }
+    }
+
+    impl fmt::Display for ByteSize {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.0 {
+                bytes if bytes >= 1_000_000 => write!(f, "{:.2}MB", bytes as f64 / 1e6),
+                bytes if bytes >= 1_000 => write!(f, "{:.1}KB", bytes as f64 / 1e3),
+                bytes => write!(f, "{}B", bytes),
+            }
+        }
+    }
+
+    /// A change made to a tile so it fits the budget
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Reduction {
+        /// The palette was cut to this many colours
+        Colors(usize),
+        /// Only `kept` of the `total` frames remain
+        Frames { kept: usize, total: usize },
+        /// The lossy encoder quality was lowered to this value
+        Quality(u8),
+    }
+
+    impl fmt::Display for Reduction {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Reduction::Colors(colors) => write!(f, "{} colours", colors),
+                Reduction::Frames { kept, total } => write!(f, "{} of {} frames", kept, total),
+                Reduction::Quality(quality) => write!(f, "quality {}", quality),
+            }
+        }
+    }
+
+    /// Logs what was changed to fit a tile into `budget`
+    pub fn report(
+        index: u32,
+        original: usize,
+        size: usize,
+        budget: ByteSize,
+        changes: &[Reduction],
+    ) {
+        let changes = changes
+            .iter()
+            .map(Reduction::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if size as u64 <= budget.0 {
+            info!(
+                "Tile {} reduced from {} to {} ({})",
+                index + 1,
+                ByteSize(original as u64),
+                ByteSize(size as u64),
+                changes
+            );
+        } else {
+            warn!(
+                "Tile {} is still {} after reducing it to {}, over the {} budget",
+                index + 1,
+                ByteSize(size as u64),
+                changes,
+                budget
+            );
+        }
+    }
+}
+
+/// Output formats and encoder settings for still tiles
+pub mod encode {
+    use super::*;
+    use clap::ValueEnum;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+    use image::RgbImage;
+    use std::collections::HashMap;
+
+    /// Format of still tiles
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+    pub enum OutputFormat {
+        /// PNG, lossless
+        Png,
+        /// JPEG at the configured quality
+        Jpeg,
+        /// Lossless WebP
+        Webp,
+        /// GIF using the configured quantizer and dithering
+        Gif,
+    }
+
+    impl OutputFormat {
+        /// Returns the image format written for this output format
+        pub fn image_format(self) -> ImageFormat {
+            match self {
+                OutputFormat::Png => ImageFormat::Png,
+                OutputFormat::Jpeg => ImageFormat::Jpeg,
+                OutputFormat::Webp => ImageFormat::WebP,
+                OutputFormat::Gif => ImageFormat::Gif,
+            }
+        }
+    }
+
+    /// zlib effort used for PNG tiles
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+    pub enum PngCompression {
+        /// Fastest encoding, largest files
+        #[default]
+        Fast,
+        /// Balance of speed and size
+        Balanced,
+        /// Slowest encoding, smallest files
+        Best,
+    }
+
+    /// Encoder settings for still tiles
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Encoding {
+        /// Format of still tiles, `None` to keep the input format
+        pub format: Option<OutputFormat>,
+        /// JPEG quality from 1 to 100
+        pub jpeg_quality: u8,
+        /// zlib effort of PNG tiles
+        pub png_compression: PngCompression,
+        /// Searches colour types and filters for the smallest lossless PNG
+        pub optimize: bool,
+    }
+
+    impl Default for Encoding {
+        fn default() -> Self {
+            Self {
+                format: None,
+                jpeg_quality: 75,
+                png_compression: PngCompression::default(),
+                optimize: false,
+            }
+        }
+    }
+
+    impl Encoding {
+        /// Checks that the JPEG quality is within 1 to 100
+        pub fn validate(&self) -> GridistResult<()> {
+            if !(1..=100).contains(&self.jpeg_quality) {
+                return Err(GridistError::InvalidConfig(vec![format!(
+                    "JPEG quality must be between 1 and 100, got {}",
+                    self.jpeg_quality
+                )]));
+            }
+            Ok(())
+        }
+
+        /// Returns whether animated input keeps its animation
+        ///
+        /// Only GIF tiles can animate, so any other format gives still tiles
+        /// of the first frame.
+        pub fn animates(&self) -> bool {
+            matches!(self.format, None | Some(OutputFormat::Gif))
+        }
+
+        /// Returns the format of tiles cut from an image in `input` format
+        pub fn output_format(&self, input: ImageFormat) -> ImageFormat {
+            self.format.map_or(input, OutputFormat::image_format)
+        }
+    }
+
+    /// Encodes a still image as `format` using the encoder settings
+    ///
+    /// JPEG has no alpha channel, so transparent pixels are blended onto
+    /// white. Formats without settings use the image crate's defaults.
+    pub fn encode_image(
+        image: &DynamicImage,
+        format: ImageFormat,
+        encoding: &Encoding,
+    ) -> GridistResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match format {
+            ImageFormat::Jpeg => {
+                JpegEncoder::new_with_quality(&mut bytes, encoding.jpeg_quality)
+                    .encode_image(&flatten(image))?;
+            }
+            // The optimised encodings are 8-bit, so deeper tiles skip them to stay lossless
+            ImageFormat::Png if encoding.optimize && bits_per_channel(image) <= 8 => {
+                let default = encode_image(
+                    image,
+                    format,
+                    &Encoding {
+                        optimize: false,
+                        ..*encoding
+                    },
+                )?;
+                let optimized = optimized_png(image)?;
+                debug!(
+                    "Optimised PNG tile from {} to {} bytes",
+                    default.len(),
+                    optimized.len()
+                );
+                bytes = if optimized.len() < default.len() {
+                    optimized
+                } else {
+                    default
+                };
+            }
+            ImageFormat::Png => {
+                let compression = match encoding.png_compression {
+                    PngCompression::Fast => CompressionType::Fast,
+                    PngCompression::Balanced => CompressionType::Default,
+                    PngCompression::Best => CompressionType::Best,
+                };
+                let encoder = PngEncoder::new_with_quality(
+                    Cursor::new(&mut bytes),
+                    compression,
+                    PngFilter::Adaptive,
+                );
+                image.write_with_encoder(encoder)?;
+            }
+            _ => image.write_to(&mut Cursor::new(&mut bytes), format)?,
+        }
+        Ok(bytes)
+    }
+
+    /// Blends the image onto white and drops the alpha channel
+    fn flatten(image: &DynamicImage) -> RgbImage {
+        if !image.color().has_alpha() {
+            return image.to_rgb8();
+        }
+        let rgba = image.to_rgba8();
+        RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+            let [r, g, b, a] = rgba.get_pixel(x, y).0;
+            let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+            image::Rgb([blend(r), blend(g), blend(b)])
+        })
+    }
+
+    /// Returns the bit depth of each channel of an image
+    fn bits_per_channel(image: &DynamicImage) -> u16 {
+        let color = image.color();
+        color.bits_per_pixel() / u16::from(color.channel_count())
+    }
+
+    /// Encodes the smallest lossless PNG found for the image
+    ///
+    /// The pixels are stored in the narrowest colour type that holds them
+    /// exactly, a palette of 1 to 8 bits, grey or RGB with alpha only when
+    /// needed, and every filter strategy is tried at the best compression.
+    /// Only 8-bit images are stored exactly.
+    fn optimized_png(image: &DynamicImage) -> GridistResult<Vec<u8>> {
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let pixels: Vec<[u8; 4]> = rgba.pixels().map(|p| p.0).collect();
+        let opaque = pixels.iter().all(|p| p[3] == 255);
+        let grey = pixels.iter().all(|p| p[0] == p[1] && p[1] == p[2]);
+
+        let mut palette: Vec<[u8; 4]> = Vec::new();
+        let mut indices: HashMap<[u8; 4], u8> = HashMap::new();
+        let fits_palette = pixels.iter().all(|&pixel| {
+            if indices.contains_key(&pixel) {
+                return true;
+            }
+            if palette.len() == 256 {
+                return false;
+            }
+            indices.insert(pixel, palette.len() as u8);
+            palette.push(pixel);
+            true
+        });
+        let depth = match palette.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+
+        let mut header = PngHeader {
+            color: png::ColorType::Rgba,
+            depth: png::BitDepth::Eight,
+            palette: Vec::new(),
+            trns: Vec::new(),
+        };
+        let data = if fits_palette && !(grey && depth == 8) {
+            // Translucent entries go first so the tRNS chunk stays short
+            palette.sort_by_key(|color| (color[3] == 255, *color));
+            let indices: HashMap<_, _> = palette
+                .iter()
+                .enumerate()
+                .map(|(i, &color)| (color, i as u8))
+                .collect();
+            header.color = png::ColorType::Indexed;
+            header.depth = match depth {
+                1 => png::BitDepth::One,
+                2 => png::BitDepth::Two,
+                4 => png::BitDepth::Four,
+                _ => png::BitDepth::Eight,
+            };
+            header.palette = palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+            header.trns = palette
+                .iter()
+                .map(|c| c[3])
+                .take_while(|&alpha| alpha < 255)
+                .collect();
+            pack_rows(
+                pixels.iter().map(|pixel| indices[pixel]),
+                width as usize,
+                depth,
+            )
+        } else {
+            let channels: &[usize] = match (grey, opaque) {
+                (true, true) => {
+                    header.color = png::ColorType::Grayscale;
+                    &[0]
+                }
+                (true, false) => {
+                    header.color = png::ColorType::GrayscaleAlpha;
+                    &[0, 3]
+                }
+                (false, true) => {
+                    header.color = png::ColorType::Rgb;
+                    &[0, 1, 2]
+                }
+                (false, false) => &[0, 1, 2, 3],
+            };
+            pixels
+                .iter()
+                .flat_map(|pixel| channels.iter().map(|&c| pixel[c]))
+                .collect()
+        };
+
+        let filters = [
+            png::FilterType::NoFilter,
+            png::FilterType::Sub,
+            png::FilterType::Up,
+            png::FilterType::Avg,
+            png::FilterType::Paeth,
+        ];
+        let strategies = filters
+            .iter()
+            .map(|&filter| (filter, png::AdaptiveFilterType::NonAdaptive))
+            .chain([(png::FilterType::Sub, png::AdaptiveFilterType::Adaptive)]);
+        let mut smallest = Vec::new();
+        for (filter, adaptive) in strategies {
+            let mut bytes = Vec::new();
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(header.color);
+            encoder.set_depth(header.depth);
+            if !header.palette.is_empty() {
+                encoder.set_palette(header.palette.as_slice());
+            }
+            if !header.trns.is_empty() {
+                encoder.set_trns(header.trns.as_slice());
+            }
+            encoder.set_compression(png::Compression::Best);
+            encoder.set_filter(filter);
+            encoder.set_adaptive_filter(adaptive);
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&data))
+                .context("Failed to encode optimised PNG")?;
+            if smallest.is_empty() || bytes.len() < smallest.len() {
+                smallest = bytes;
+            }
+        }
+        Ok(smallest)
+    }
+
+    /// Colour type and palette chunks of an optimised PNG
+    struct PngHeader {
+        color: png::ColorType,
+        depth: png::BitDepth,
+        palette: Vec<u8>,
+        trns: Vec<u8>,
+    }
+
+    /// Packs palette indices into rows of `depth`-bit samples
+    ///
+    /// Each row starts on a byte boundary, as PNG requires.
+    fn pack_rows(indices: impl Iterator<Item = u8>, width: usize, depth: usize) -> Vec<u8> {
+        if depth == 8 {
+            return indices.collect();
+        }
+        let per_byte = 8 / depth;
+        let row_bytes = width.div_ceil(per_byte);
+        let indices: Vec<u8> = indices.collect();
+        let mut data = Vec::with_capacity(row_bytes * indices.len() / width.max(1));
+        for row in indices.chunks(width.max(1)) {
+            for samples in row.chunks(per_byte) {
+                let byte = samples.iter().enumerate().fold(0u8, |byte, (i, &index)| {
+                    byte | index << (8 - depth * (i + 1))
+                });
+                data.push(byte);
+            }
+        }
+        data
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn optimized(image: &DynamicImage) -> DynamicImage {
+            let encoding = Encoding {
+                optimize: true,
+                ..Encoding::default()
+            };
+            let bytes = encode_image(image, ImageFormat::Png, &encoding).unwrap();
+            image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap()
+        }
+
+        #[test]
+        fn optimize_keeps_16_bit_tiles_lossless() {
+            // Noise, so an 8-bit encoding would be smaller than the 16-bit one
+            let mut state = 0x2545_f491u32;
+            let image = DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(32, 8, |_, _| {
+                image::Rgb([(); 3].map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u16
+                }))
+            }));
+            let decoded = optimized(&image);
+            assert_eq!(decoded.color(), image::ColorType::Rgb16);
+            assert_eq!(decoded.as_bytes(), image.as_bytes());
+        }
+
+        #[test]
+        fn optimize_keeps_8_bit_tiles_lossless() {
+            let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 8, |x, y| {
+                image::Rgba([
+                    (x * 8) as u8,
+                    (y * 30) as u8,
+                    7,
+                    if x < 4 { 0 } else { 255 },
+                ])
+            }));
+            assert_eq!(optimized(&image).to_rgba8(), image.to_rgba8());
+        }
+    }
+}
+
+/// Local preview of how the grid looks on a GitHub profile
+pub mod preview {
+    use super::*;
+    use crate::animation;
+    use crate::cropper::{self, ImageCropper};
+    use clap::ValueEnum;
+    use image::codecs::gif::{GifEncoder, Repeat as GifRepeat};
+    use image::{Delay, Rgba};
+
+    /// Colour scheme of the mocked profile page
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+    pub enum Theme {
+        /// GitHub's light theme
+        #[default]
+        Light,
+        /// GitHub's dark theme
+        Dark,
+    }
+
+    /// Colours used to draw the pinned-card container
+    struct Palette {
+        page: Rgba<u8>,
+        card: Rgba<u8>,
+        border: Rgba<u8>,
+        title: Rgba<u8>,
+    }
+
+    impl Theme {
+        fn palette(self) -> Palette {
+            match self {
+                Theme::Light => Palette {
+                    page: Rgba([255, 255, 255, 255]),
+                    card: Rgba([255, 255, 255, 255]),
+                    border: Rgba([208, 215, 222, 255]),
+                    title: Rgba([9, 105, 218, 255]),
+                },
+                Theme::Dark => Palette {
+                    page: Rgba([13, 17, 23, 255]),
+                    card: Rgba([13, 17, 23, 255]),
+                    border: Rgba([48, 54, 61, 255]),
+                    title: Rgba([68, 147, 248, 255]),
+                },
+            }
+        }
+    }
+
+    /// Renders cropped tiles back onto a mock of the pinned-card container
+    pub struct PreviewRenderer {
+        cropper: ImageCropper,
+        theme: Theme,
+    }
+
+    impl PreviewRenderer {
+        /// Creates a new PreviewRenderer using the cropper's layout
+        pub fn new(cropper: ImageCropper, theme: Theme) -> Self {
+            Self { cropper, theme }
+        }
+
+        /// Composites a set of tiles, in grid order, onto the container mock
+        pub fn render(&self, tiles: &[RgbaImage]) -> RgbaImage {
+            let config = self.cropper.config();
+            let palette = self.theme.palette();
+            let mut canvas = RgbaImage::from_pixel(
+                config.container_width,
+                config.minimum_height(),
+                palette.page,
+            );
+
+            for (i, tile) in tiles.iter().enumerate() {
+                let (x, y) = self.cropper.get_xy(i as u32);
+                let card_x = x - config.card_padding_horizontal;
+                let card_y = y - config.card_padding_top;
+                let card_width = config.cut_width + 2 * config.card_padding_horizontal;
+                let card_height = config.card_height();
+
+                fill_rect(
+                    &mut canvas,
+                    card_x,
+                    card_y,
+                    card_width,
+                    card_height,
+                    palette.border,
+                );
+                fill_rect(
+                    &mut canvas,
+                    card_x + 1,
+                    card_y + 1,
+                    card_width.saturating_sub(2),
+                    card_height.saturating_sub(2),
+                    palette.card,
+                );
+
+                // Stand-in for the gist file name shown above the image
+                if config.card_padding_top >= 16 {
+                    fill_rect(
+                        &mut canvas,
+                        x,
+                        card_y + config.card_padding_top / 2 - 4,
+                        config.cut_width / 3,
+                        8,
+                        palette.title,
+                    );
+                }
+
+                image::imageops::overlay(&mut canvas, tile, x as i64, y as i64);
+            }
+
+            canvas
+        }
+
+        /// Renders a static image preview and saves it to `output`
+        pub fn render_image(&self, input: &Path, output: &Path) -> GridistResult<()> {
+            info!("Rendering preview of {}", input.display());
+            let bytes = fs::read(input)
+                .with_context(|| format!("Failed to read file: {}", input.display()))?;
+            let format = cropper::detect_format(&bytes)?;
+            let image = image::load_from_memory_with_format(&bytes, format)
+                .context("Failed to open image")?;
+            let tiles = self.cropper.crop_tiles(&image);
+            self.render(&tiles).save(output)?;
+            info!("Saved preview to {}", output.display());
+            Ok(())
+        }
+
+        /// Renders an animated GIF, APNG or WebP preview and saves it to `output`
+        pub fn render_animation(&self, input: &Path, output: &Path) -> GridistResult<()> {
+            info!("Rendering animated preview of {}", input.display());
+            let bytes = fs::read(input)
+                .with_context(|| format!("Failed to read file: {}", input.display()))?;
+            let format = cropper::detect_format(&bytes)?;
+            let mut source = animation::open(&bytes, format)?
+                .ok_or_else(|| anyhow::anyhow!("{} is not an animated image", input.display()))?;
+
+            let mut frames = Vec::new();
+            while let Some(frame) = source.next_frame()? {
+                frames.push(frame);
+            }
+
+            // One placement for every frame, so the preview doesn't jump
+            let (images, delays): (Vec<_>, Vec<_>) = frames
+                .into_iter()
+                .map(|frame| (frame.image, frame.delay))
+                .unzip();
+            let offsets = self.cropper.animation_offsets(&images);
+            let rendered: Vec<_> = images
+                .into_par_iter()
+                .zip(delays)
+                .map(|(image, delay)| {
+                    let delay = Delay::from_numer_denom_ms(u32::from(delay) * 10, 1);
+                    let tiles = self.cropper.crop_frame(&image, offsets);
+                    image::Frame::from_parts(self.render(&tiles), 0, 0, delay)
+                })
+                .collect();
+
+            let output_file = File::create(output)
+                .with_context(|| format!("Failed to create output file: {}", output.display()))?;
+            let mut encoder = GifEncoder::new_with_speed(output_file, 10);
+            encoder.set_repeat(GifRepeat::Infinite)?;
+            encoder.encode_frames(rendered)?;
+            info!("Saved animated preview to {}", output.display());
+            Ok(())
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas bounds
+    fn fill_rect(canvas: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+        let x_end = (x + width).min(canvas.width());
+        let y_end = (y + height).min(canvas.height());
+        for py in y..y_end {
+            for px in x..x_end {
+                canvas.put_pixel(px, py, color);
+            }
         }
     }
 }
@@ -3,6 +3,8 @@ Gridist CLI - Command line interface for the Gridist image grid tool
 
 This binary provides a command-line interface to:
 - Upload images and convert them to grid layouts on GitHub Gists
+- Split images into grid tiles without uploading
+- Preview the grid locally before uploading
 - Manage existing gists through an interactive TUI
 
 # Usage
@@ -11,6 +13,15 @@ This binary provides a command-line interface to:
 # Upload an image
 gridist upload image.png -t <github_token>
 
+# Only write the tiles to a directory
+gridist split image.png --out-dir tiles --name "{stem}-r{row}c{col}"
+
+# Keep the first five seconds of an animation at up to 15 FPS
+gridist upload anim.gif --max-fps 15 --trim 0:00-0:05
+
+# Preview the grid without uploading
+gridist preview image.png -o preview.png
+
 # Manage gists
 gridist manage -t <github_token>
 ```
@@ -18,11 +29,24 @@ gridist manage -t <github_token>
 The GitHub token can also be provided via the GITHUB_TOKEN environment variable.
 */
 
-use clap::{Parser, Subcommand};
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
 use gridist::{
-    config::ImageConfig, cropper::ImageCropper, github::GithubUploader, tui::GistManager,
+    animation::{self, Timing, TrimRange},
+    budget::ByteSize,
+    config::{Background, Fit, ImageConfig, ResizeFilter},
+    cropper::{
+        detect_format, CropRect, FocusPoint, Framing, GutterOptions, ImageCropper,
+        DEFAULT_NAME_TEMPLATE,
+    },
+    dither::Dither,
+    encode::{Encoding, OutputFormat, PngCompression},
+    github::GithubUploader,
+    preview::{PreviewRenderer, Theme},
+    quantize::Quantizer,
+    tui::GistManager,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{info, Level};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
@@ -50,6 +74,55 @@ enum Commands {
         /// GitHub personal access token
         #[arg(short, long, env = "GITHUB_TOKEN")]
         token: String,
+
+        #[command(flatten)]
+        layout: LayoutArgs,
+
+        #[command(flatten)]
+        gif: GifArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Split an image into grid tiles without uploading
+    Split {
+        /// Path to the image file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Directory the tiles are written to
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        out_dir: PathBuf,
+
+        /// Tile naming template using {stem}, {index}, {row}, {col} and {ext}
+        #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_NAME_TEMPLATE)]
+        name: String,
+
+        #[command(flatten)]
+        layout: LayoutArgs,
+
+        #[command(flatten)]
+        gif: GifArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Render a local preview of the grid on a mocked profile
+    Preview {
+        /// Path to the image file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Path of the preview image (defaults to preview.png, or preview.gif for GIFs)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Colour theme of the mocked profile
+        #[arg(long, value_enum, default_value_t = Theme::Light)]
+        theme: Theme,
+
+        #[command(flatten)]
+        layout: LayoutArgs,
     },
     /// Manage uploaded gists
     Manage {
@@ -59,10 +132,199 @@ enum Commands {
     },
 }
 
+/// Options selecting the grid layout
+#[derive(Args)]
+struct LayoutArgs {
+    /// Layout file (TOML/JSON) or built-in preset name
+    #[arg(long, value_name = "FILE|PRESET", default_value = "github-pinned-2024")]
+    layout: String,
+
+    /// Number of rows in the grid, overriding the layout
+    #[arg(long)]
+    rows: Option<u32>,
+
+    /// Number of columns in the grid, overriding the layout
+    #[arg(long)]
+    cols: Option<u32>,
+
+    /// How the image is scaled to the grid, overriding the layout
+    #[arg(long, value_enum)]
+    fit: Option<Fit>,
+
+    /// Fill for contain and pad: transparent, blur, white, black or #rrggbb[aa]
+    #[arg(long, value_name = "COLOR|blur")]
+    background: Option<Background>,
+
+    /// Resampling filter, overriding the layout; nearest keeps pixel art sharp
+    #[arg(long, value_enum)]
+    filter: Option<ResizeFilter>,
+
+    /// Point the grid is centred on, from 0,0 (top-left) to 1,1 (bottom-right)
+    #[arg(long, value_name = "X,Y", conflicts_with = "crop")]
+    focus: Option<FocusPoint>,
+
+    /// Part of the source, in pixels, that is fitted to the grid
+    #[arg(long, value_name = "X,Y,W,H", conflicts_with = "smart_crop")]
+    crop: Option<CropRect>,
+
+    /// Place the grid where the visible tiles keep the most edge detail
+    #[arg(long, conflicts_with = "focus")]
+    smart_crop: bool,
+
+    /// Log how much edge detail the gutters between cards hide
+    #[arg(long)]
+    gutter_report: bool,
+
+    /// Move the grid up to this many pixels so the gutters hide less detail
+    #[arg(long, value_name = "PX", default_value_t = 0)]
+    gutter_shift: u32,
+}
+
+impl LayoutArgs {
+    /// Builds an ImageCropper from the selected layout and overrides
+    fn cropper(&self) -> anyhow::Result<ImageCropper> {
+        let mut config = ImageConfig::from_layout(&self.layout)?;
+        config.rows = self.rows.unwrap_or(config.rows);
+        config.cols = self.cols.unwrap_or(config.cols);
+        config.fit = self.fit.unwrap_or(config.fit);
+        config.background = self.background.unwrap_or(config.background);
+        config.filter = self.filter.unwrap_or(config.filter);
+        let mut cropper = ImageCropper::new(config)?;
+        let framing = match (self.focus, self.crop) {
+            (Some(focus), _) => Framing::Focus(focus),
+            (None, Some(rect)) => Framing::Region(rect),
+            (None, None) if self.smart_crop => Framing::Smart,
+            (None, None) => Framing::Center,
+        };
+        cropper.set_framing(framing)?;
+        cropper.set_gutters(GutterOptions {
+            report: self.gutter_report,
+            max_shift: self.gutter_shift,
+        });
+        cropper.set_progress(true);
+        Ok(cropper)
+    }
+}
+
+/// Options for animated GIF output
+#[derive(Args)]
+struct GifArgs {
+    /// How the palette of GIF tiles is chosen
+    #[arg(long, value_enum, default_value_t = Quantizer::Source)]
+    quantizer: Quantizer,
+
+    /// Dithering applied when mapping GIF tiles to their palette
+    #[arg(long, value_enum, default_value_t = Dither::None)]
+    dither: Dither,
+
+    /// Highest frame rate of GIF tiles, merging the delays of dropped frames
+    #[arg(long, value_name = "FPS")]
+    max_fps: Option<f32>,
+
+    /// Part of the animation to keep, e.g. 0:00-0:05 or 1.5-
+    #[arg(long, value_name = "START-END")]
+    trim: Option<TrimRange>,
+
+    /// Highest number of frames in GIF tiles, dropped evenly
+    #[arg(long, value_name = "N")]
+    max_frames: Option<usize>,
+
+    /// Playback speed factor of GIF tiles
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    speed: f32,
+}
+
+impl GifArgs {
+    /// Applies the GIF options to an ImageCropper
+    fn apply(&self, cropper: &mut ImageCropper) -> anyhow::Result<()> {
+        cropper.set_quantizer(self.quantizer);
+        cropper.set_dither(self.dither);
+        cropper.set_timing(Timing {
+            max_fps: self.max_fps,
+            trim: self.trim,
+            max_frames: self.max_frames,
+            speed: self.speed,
+        })?;
+        Ok(())
+    }
+}
+
+/// Options for the encoded tiles
+#[derive(Args)]
+struct OutputArgs {
+    /// Largest size of a tile, e.g. 2MB; larger tiles are re-encoded smaller
+    #[arg(long, value_name = "SIZE")]
+    max_tile_size: Option<ByteSize>,
+
+    /// Format of the tiles, defaulting to the input's; only GIF tiles animate
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// JPEG quality of the tiles, from 1 to 100
+    #[arg(long, default_value_t = 75)]
+    quality: u8,
+
+    /// Compression level of PNG tiles
+    #[arg(long, value_enum, default_value_t = PngCompression::Fast)]
+    png_compression: PngCompression,
+
+    /// Search for the smallest lossless encoding of PNG tiles
+    #[arg(long)]
+    optimize: bool,
+}
+
+impl OutputArgs {
+    /// Applies the output options to an ImageCropper
+    fn apply(&self, cropper: &mut ImageCropper) -> anyhow::Result<()> {
+        cropper.set_max_tile_size(self.max_tile_size);
+        cropper.set_encoding(Encoding {
+            format: self.format,
+            jpeg_quality: self.quality,
+            png_compression: self.png_compression,
+            optimize: self.optimize,
+        })?;
+        Ok(())
+    }
+}
+
+/// Returns whether a file is an animated GIF, APNG or WebP
+///
+/// The format is detected from the contents, so the extension and its case
+/// do not matter.
+fn is_animated(file: &Path) -> anyhow::Result<bool> {
+    let bytes =
+        std::fs::read(file).with_context(|| format!("Failed to read file: {}", file.display()))?;
+    let format = detect_format(&bytes)?;
+    Ok(animation::is_animated(&bytes, format))
+}
+
+/// Crops a file into tiles, choosing the animation or static image pipeline
+fn crop_file(cropper: &ImageCropper, file: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let animated = is_animated(file)?;
+    let cropped_files = if animated && cropper.encoding().animates() {
+        info!("Processing animated image file");
+        cropper.crop_animation(file)?
+    } else if animated {
+        info!("Writing still tiles of the first frame");
+        cropper.crop_image(file)?
+    } else {
+        info!("Processing static image file");
+        cropper.crop_image(file)?
+    };
+
+    info!(
+        "Successfully cropped image into {} files",
+        cropped_files.len()
+    );
+    Ok(cropped_files)
+}
+
 /// Entry point for the Gridist CLI application
 ///
 /// Sets up logging based on the command and handles:
 /// - Image/GIF processing and upload to GitHub Gists
+/// - Splitting images into tiles on disk
+/// - Local preview rendering
 /// - Interactive TUI for gist management
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -70,8 +332,8 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize tracing subscriber based on command
     match cli.command {
-        Commands::Upload { .. } => {
-            // For Upload command, use normal logging
+        Commands::Upload { .. } | Commands::Split { .. } | Commands::Preview { .. } => {
+            // For Upload, Split and Preview commands, use normal logging
             FmtSubscriber::builder()
                 .with_env_filter(
                     EnvFilter::from_default_env()
@@ -100,27 +362,55 @@ async fn main() -> anyhow::Result<()> {
     }
 
     match cli.command {
-        Commands::Upload { file, token } => {
+        Commands::Upload {
+            file,
+            token,
+            layout,
+            gif,
+            output,
+        } => {
             info!("Starting image upload process for file: {}", file.display());
-            let config = ImageConfig::default();
-            let cropper = ImageCropper::new(config);
+            let mut cropper = layout.cropper()?;
+            gif.apply(&mut cropper)?;
+            output.apply(&mut cropper)?;
             let uploader = GithubUploader::new(token);
 
-            let cropped_files = if file.extension().map_or(false, |ext| ext == "gif") {
-                info!("Processing GIF file");
-                cropper.crop_gif(&file)?
-            } else {
-                info!("Processing static image file");
-                cropper.crop_image(&file)?
-            };
-
-            info!(
-                "Successfully cropped image into {} files",
-                cropped_files.len()
-            );
+            let cropped_files = crop_file(&cropper, &file)?;
             uploader.upload_files(cropped_files).await?;
             info!("Upload process completed successfully");
         }
+        Commands::Split {
+            file,
+            out_dir,
+            name,
+            layout,
+            gif,
+            output,
+        } => {
+            let mut cropper = layout.cropper()?;
+            gif.apply(&mut cropper)?;
+            output.apply(&mut cropper)?;
+            cropper.set_output_dir(out_dir);
+            cropper.set_name_template(name)?;
+            for path in crop_file(&cropper, &file)? {
+                println!("{}", path.display());
+            }
+        }
+        Commands::Preview {
+            file,
+            output,
+            theme,
+            layout,
+        } => {
+            let renderer = PreviewRenderer::new(layout.cropper()?, theme);
+            if is_animated(&file)? {
+                let output = output.unwrap_or_else(|| PathBuf::from("preview.gif"));
+                renderer.render_animation(&file, &output)?;
+            } else {
+                let output = output.unwrap_or_else(|| PathBuf::from("preview.png"));
+                renderer.render_image(&file, &output)?;
+            }
+        }
         Commands::Manage { token } => {
             let uploader = GithubUploader::new(token);
             let mut manager = GistManager::new(uploader);